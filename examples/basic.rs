@@ -50,11 +50,11 @@ fn main() -> Result<(), Box<dyn Error>> {
     //           |-------- Child 1
     //                     |-------- Grandchild
 
-    print_tree::<Tree>(&world, root);
+    print!("{}", format_tree::<Tree>(&world, root));
 
     world.despawn_all::<Tree>(child2);
 
-    print_tree::<Tree>(&world, root);
+    print!("{}", format_tree::<Tree>(&world, root));
 
     world
         .iter()
@@ -63,23 +63,6 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn print_tree<T: 'static + Send + Sync>(world: &World, root: Entity) {
-    fn internal<T: 'static + Send + Sync>(world: &World, parent: Entity, depth: usize) {
-        for child in world.children::<T>(parent) {
-            let name = world.get::<&&str>(child).unwrap();
-            println!(
-                "{}|-------- {}",
-                std::iter::repeat(" ")
-                    .take((depth - 1) * 10)
-                    .collect::<String>(),
-                *name,
-            );
-
-            internal::<T>(world, child, depth + 1)
-        }
-    }
-
-    let name = world.get::<&&str>(root).unwrap();
-    println!("{}", *name);
-    internal::<T>(world, root, 1)
+fn format_tree<T: hecs::Component>(world: &World, root: Entity) -> String {
+    world.format_tree::<T, _>(root, |e| world.get::<&&str>(e).unwrap().to_string())
 }
@@ -4,7 +4,7 @@ use hecs::{Component, DynamicBundle, Entity, EntityBuilder, World};
 use hecs_schedule::{CommandBuffer, GenericWorld};
 use once_cell::sync::OnceCell;
 
-use crate::{HierarchyMut, TreeBuilderClone};
+use crate::{hierarchy::link_new_children, HierarchyMut, TreeBuilderClone};
 
 /// Ergonomically construct trees without knowledge of world.
 ///
@@ -24,11 +24,11 @@ use crate::{HierarchyMut, TreeBuilderClone};
 ///     builder.add("child 2");
 ///     builder
 /// });
-
+///
 /// let root = builder.spawn(&mut world);
-
+///
 /// assert_eq!(*world.get::<&&'static str>(root).unwrap(), "root");
-
+///
 /// for (a, b) in world
 ///     .descendants_depth_first::<Tree>(root)
 ///     .zip(["child 1", "child 2"])
@@ -46,6 +46,7 @@ pub struct TreeBuilder<T> {
 
 impl<T: Component> TreeBuilder<T> {
     /// Construct a new empty tree
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
             children: Vec::new(),
@@ -55,6 +56,16 @@ impl<T: Component> TreeBuilder<T> {
         }
     }
 
+    /// Construct a new empty tree with space for `capacity` children
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            children: Vec::with_capacity(capacity),
+            builder: EntityBuilder::new(),
+            marker: PhantomData,
+            reserved: OnceCell::new(),
+        }
+    }
+
     /// Reserve the entity which this node will spawn
     pub fn reserve(&self, world: &impl GenericWorld) -> Entity {
         *self.reserved.get_or_init(|| world.reserve())
@@ -62,14 +73,39 @@ impl<T: Component> TreeBuilder<T> {
 
     /// Spawn the whole tree into the world
     pub fn spawn(&mut self, world: &mut World) -> Entity {
+        self.spawn_collect(world, None)
+    }
+
+    /// Spawn the whole tree into the world. Returns the root along with all spawned descendants
+    /// in depth first order.
+    pub fn spawn_with_entities(&mut self, world: &mut World) -> (Entity, Vec<Entity>) {
+        let mut entities = Vec::new();
+        let root = self.spawn_collect(world, Some(&mut entities));
+        (root, entities)
+    }
+
+    /// Spawns the tree, pushing the descendants to `entities` in depth first order if given
+    fn spawn_collect(
+        &mut self,
+        world: &mut World,
+        mut entities: Option<&mut Vec<Entity>>,
+    ) -> Entity {
         let parent = self.reserve(world);
         let builder = self.builder.build();
         world.insert(parent, builder).unwrap();
 
-        for mut child in self.children.drain(..) {
-            let child = child.spawn(world);
-            world.attach::<T>(child, parent).unwrap();
-        }
+        let children = self
+            .children
+            .drain(..)
+            .map(|mut child| {
+                if let Some(entities) = entities.as_deref_mut() {
+                    entities.push(child.reserve(world));
+                }
+                child.spawn_collect(world, entities.as_deref_mut())
+            })
+            .collect::<Vec<_>>();
+
+        link_new_children::<T>(world, parent, &children).unwrap();
 
         parent
     }
@@ -77,12 +113,39 @@ impl<T: Component> TreeBuilder<T> {
     /// Spawn the whole tree into a commandbuffer.
     /// The world is required for reserving entities.
     pub fn spawn_deferred(&mut self, world: &impl GenericWorld, cmd: &mut CommandBuffer) -> Entity {
+        self.spawn_deferred_collect(world, cmd, None)
+    }
+
+    /// Spawn the whole tree into a commandbuffer. Returns the reserved root along with all
+    /// reserved descendants in depth first order.
+    /// The world is required for reserving entities.
+    pub fn spawn_deferred_with_entities(
+        &mut self,
+        world: &impl GenericWorld,
+        cmd: &mut CommandBuffer,
+    ) -> (Entity, Vec<Entity>) {
+        let mut entities = Vec::new();
+        let root = self.spawn_deferred_collect(world, cmd, Some(&mut entities));
+        (root, entities)
+    }
+
+    /// Spawns the tree into `cmd`, pushing the reserved descendants to `entities` in depth first
+    /// order if given
+    fn spawn_deferred_collect(
+        &mut self,
+        world: &impl GenericWorld,
+        cmd: &mut CommandBuffer,
+        mut entities: Option<&mut Vec<Entity>>,
+    ) -> Entity {
         let parent = self.reserve(world);
         let builder = self.builder.build();
         cmd.insert(parent, builder);
 
         for mut child in self.children.drain(..) {
-            let child = child.spawn_deferred(world, cmd);
+            if let Some(entities) = entities.as_deref_mut() {
+                entities.push(child.reserve(world));
+            }
+            let child = child.spawn_deferred_collect(world, cmd, entities.as_deref_mut());
             cmd.write(move |w: &mut World| {
                 w.attach::<T>(child, parent).unwrap();
             });
@@ -123,6 +186,19 @@ impl<T: Component> TreeBuilder<T> {
         self
     }
 
+    /// Attach an already spawned entity, along with any existing subtree. When spawning, the
+    /// entity is attached in place instead of spawning a new one, and is detached from its
+    /// previous parent first.
+    pub fn attach_existing(&mut self, entity: Entity) -> &mut Self {
+        self.children.push(Self {
+            children: Vec::new(),
+            builder: EntityBuilder::new(),
+            marker: PhantomData,
+            reserved: OnceCell::with_value(entity),
+        });
+        self
+    }
+
     /// Consuming variant of [Self::attach].
     ///
     /// This is useful for nesting to alleviate the need to save an intermediate
@@ -159,6 +235,17 @@ impl<T: Component> TreeBuilder<T> {
     pub fn children_mut(&mut self) -> &mut Vec<TreeBuilder<T>> {
         &mut self.children
     }
+
+    /// Iterate all nodes of the tree depth first, starting with the root.
+    pub fn iter_depth_first(&self) -> impl Iterator<Item = &Self> {
+        let mut stack = vec![self];
+
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
 }
 
 impl<B: DynamicBundle, T: Component> From<B> for TreeBuilder<T> {
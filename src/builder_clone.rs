@@ -16,6 +16,7 @@ pub struct TreeBuilderClone<T> {
 
 impl<T: Component> TreeBuilderClone<T> {
     /// Construct a new empty tree
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
             children: Vec::new(),
@@ -25,6 +26,16 @@ impl<T: Component> TreeBuilderClone<T> {
         }
     }
 
+    /// Construct a new empty tree with space for `capacity` children
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            children: Vec::with_capacity(capacity),
+            builder: EntityBuilderClone::new(),
+            marker: PhantomData,
+            reserved: OnceCell::new(),
+        }
+    }
+
     /// Reserve the entity which this node will spawn
     pub fn reserve(&self, world: &impl GenericWorld) -> Entity {
         *self.reserved.get_or_init(|| world.reserve())
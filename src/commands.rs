@@ -0,0 +1,49 @@
+use hecs::{Component, Entity, World};
+use hecs_schedule::CommandBuffer;
+
+use crate::HierarchyMut;
+
+/// Deferred hierarchy operations for use in systems which can not borrow the world mutably.
+/// The operations are applied when the command buffer is executed.
+///
+/// Errors, such as the entities having been despawned before the buffer is executed, are ignored
+/// as there is no way to report them.
+///
+/// # Example
+/// ```rust
+/// use hecs_hierarchy::*;
+/// use hecs::*;
+/// use hecs_schedule::CommandBuffer;
+///
+/// struct Marker;
+/// let mut world = World::default();
+/// let root = world.spawn(("root",));
+/// let child = world.spawn(("child",));
+///
+/// let mut cmd = CommandBuffer::new();
+/// cmd.attach_deferred::<Marker>(child, root);
+/// cmd.execute(&mut world);
+///
+/// assert_eq!(world.parent::<Marker>(child).unwrap(), root);
+/// ```
+pub trait HierarchyCommandBuffer {
+    /// Attach `child` to `parent` when the buffer is executed. See [HierarchyMut::attach].
+    fn attach_deferred<T: Component>(&mut self, child: Entity, parent: Entity);
+
+    /// Detach `child` from its parent when the buffer is executed. See [HierarchyMut::detach].
+    fn detach_deferred<T: Component>(&mut self, child: Entity);
+}
+
+impl HierarchyCommandBuffer for CommandBuffer {
+    fn attach_deferred<T: Component>(&mut self, child: Entity, parent: Entity) {
+        self.write(move |w: &mut World| {
+            let _ = w.attach::<T>(child, parent);
+        });
+    }
+
+    fn detach_deferred<T: Component>(&mut self, child: Entity) {
+        self.write(move |w: &mut World| {
+            let _ = w.detach::<T>(child);
+        });
+    }
+}
@@ -3,20 +3,26 @@ use std::marker::PhantomData;
 use hecs::{Entity, View};
 use hecs_schedule::{error::Result, GenericWorld};
 
+use crate::ChildrenIter;
+
 /// Component of a entity with descendents in hierarchy tree `T`.
 /// Children represent a circular linked list. Since `Parent` and child is generic over a marker
 /// type, several hierarchies can coexist.
 pub struct Parent<T> {
     pub(crate) num_children: usize,
+    pub(crate) first_child: Entity,
     pub(crate) last_child: Entity,
+    pub(crate) subtree_size: usize,
     marker: PhantomData<T>,
 }
 
 impl<T: 'static + Send + Sync> Parent<T> {
-    pub(crate) fn new(num_children: usize, last_child: Entity) -> Self {
+    pub(crate) fn new(num_children: usize, first_child: Entity, last_child: Entity) -> Self {
         Self {
             num_children,
+            first_child,
             last_child,
+            subtree_size: 0,
             marker: PhantomData,
         }
     }
@@ -26,29 +32,45 @@ impl<T: 'static + Send + Sync> Parent<T> {
         self.num_children
     }
 
-    /// Query the parent's first child.
-    pub fn first_child<W: GenericWorld>(&self, world: &W) -> Result<Entity> {
-        Ok(world.try_get::<Child<T>>(self.last_child)?.next)
+    /// Return the number of descendants of the parent, not including the parent itself.
+    pub fn subtree_size(&self) -> usize {
+        self.subtree_size
+    }
+
+    /// Return the parent's first child.
+    pub fn first_child(&self) -> Entity {
+        self.first_child
     }
 
-    /// Query the parent's first child.
+    /// Query the parent's first child, failing if it is not a child in `view`.
     pub fn view_first_child(&self, view: &View<&Child<T>>) -> Result<Entity> {
-        Ok(view
-            .get(self.last_child)
-            .ok_or_else(|| hecs_schedule::Error::NoSuchEntity(self.last_child))?
-            .next)
+        view.get(self.first_child)
+            .map(|_| self.first_child)
+            .ok_or(hecs_schedule::Error::NoSuchEntity(self.first_child))
     }
     /// Return the parent's last child.
     pub fn last_child(&self) -> Entity {
         self.last_child
     }
+
+    /// Iterates the parent's children, without querying the parent again.
+    pub fn children<'a, W: GenericWorld>(&self, world: &'a W) -> ChildrenIter<'a, T> {
+        ChildrenIter::new(
+            world,
+            self.num_children,
+            Some(self.first_child),
+            Some(self.last_child),
+        )
+    }
 }
 
 impl<T> std::fmt::Debug for Parent<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Parent")
             .field("num_children", &self.num_children)
+            .field("first_child", &self.first_child)
             .field("last_child", &self.last_child)
+            .field("subtree_size", &self.subtree_size)
             .finish()
     }
 }
@@ -83,3 +105,42 @@ impl<T> std::fmt::Debug for Child<T> {
             .finish()
     }
 }
+
+/// Opt-in component persisting the position of a child among its siblings in tree `T`.
+/// Since the order of children otherwise only lives in the linked list, this allows restoring the
+/// order of a tree rebuilt from e.g. serialized components. See
+/// [HierarchyMut::attach_ordered](crate::HierarchyMut::attach_ordered) and
+/// [HierarchyMut::restore_order](crate::HierarchyMut::restore_order).
+pub struct OrderIndex<T> {
+    index: u32,
+    marker: PhantomData<T>,
+}
+
+impl<T> OrderIndex<T> {
+    /// Construct a new order index
+    pub fn new(index: u32) -> Self {
+        Self {
+            index,
+            marker: PhantomData,
+        }
+    }
+
+    /// Return the index.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl<T> Clone for OrderIndex<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for OrderIndex<T> {}
+
+impl<T> std::fmt::Debug for OrderIndex<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("OrderIndex").field(&self.index).finish()
+    }
+}
@@ -0,0 +1,34 @@
+use hecs::Entity;
+
+/// A single inconsistency found in a hierarchy by [crate::Hierarchy::validate].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyDefect {
+    /// `num_children` of the parent does not match the length of its list of children.
+    ChildCountMismatch {
+        parent: Entity,
+        num_children: usize,
+        actual: usize,
+    },
+    /// `first_child` of the parent is not the child following its `last_child`.
+    FirstChildMismatch {
+        parent: Entity,
+        first_child: Entity,
+        expected: Entity,
+    },
+    /// The cached subtree size of the parent does not match its number of descendants.
+    SubtreeSizeMismatch {
+        parent: Entity,
+        subtree_size: usize,
+        actual: usize,
+    },
+    /// An entity linked into the list of children of `parent` has no `Child` component.
+    MissingChild { parent: Entity, child: Entity },
+    /// A child in the list of `parent` refers to another entity as its parent.
+    WrongParent {
+        parent: Entity,
+        child: Entity,
+        found: Entity,
+    },
+    /// The `prev` link of `next` does not point back to `child`.
+    BrokenLink { child: Entity, next: Entity },
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+
+use hecs::Entity;
+
+/// Result type of the hierarchy operations.
+pub type Result<T> = std::result::Result<T, HierarchyError>;
+
+/// Errors returned by the hierarchy operations.
+#[derive(Debug)]
+pub enum HierarchyError {
+    /// The entity is not a child in the tree, e.g. because it is a root.
+    NotAChild(Entity),
+    /// The entity is not a parent in the tree.
+    NotAParent(Entity),
+    /// The entity is not a root in the tree.
+    NotARoot(Entity),
+    /// The two entities do not share the same parent.
+    NotSiblings(Entity, Entity),
+    /// The operation would make, or ran into, an entity which is its own ancestor.
+    Cycle(Entity),
+    /// The entity is listed as a child more than once.
+    DuplicateChild(Entity),
+    /// The entity does not exist in the world.
+    NoSuchEntity(Entity),
+    /// Any other error from the underlying world.
+    Schedule(hecs_schedule::Error),
+}
+
+impl fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAChild(e) => write!(f, "Entity {:?} is not a child", e),
+            Self::NotAParent(e) => write!(f, "Entity {:?} is not a parent", e),
+            Self::NotARoot(e) => write!(f, "Entity {:?} is not a root", e),
+            Self::NotSiblings(a, b) => write!(f, "Entities {:?} and {:?} are not siblings", a, b),
+            Self::Cycle(e) => write!(f, "Entity {:?} would be its own ancestor", e),
+            Self::DuplicateChild(e) => write!(f, "Entity {:?} is listed as a child twice", e),
+            Self::NoSuchEntity(e) => write!(f, "Entity {:?} does not exist", e),
+            Self::Schedule(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for HierarchyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Schedule(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<hecs_schedule::Error> for HierarchyError {
+    fn from(err: hecs_schedule::Error) -> Self {
+        match err {
+            hecs_schedule::Error::NoSuchEntity(e) => Self::NoSuchEntity(e),
+            err => Self::Schedule(err),
+        }
+    }
+}
@@ -1,20 +1,63 @@
-use std::mem;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    iter, mem,
+};
 
-use hecs::{Component, DynamicBundle, Entity, QueryBorrow, Without, World};
-use hecs_schedule::{error::Result, GenericWorld};
+use hecs::{
+    Component, DynamicBundle, Entity, EntityBuilderClone, Query, QueryBorrow, QueryShared, View,
+    Without, World,
+};
+use hecs_schedule::{CommandBuffer, GenericWorld};
 
 use crate::{
-    AncestorIter, BreadthFirstIterator, Child, ChildrenIter, DepthFirstIterator, DepthFirstVisitor,
-    Parent,
+    error::Result, AncestorIter, BreadthFirstDepthIterator, BreadthFirstIterator,
+    BreadthFirstVisitor, Child, ChildrenIter, DepthFirstCursor, DepthFirstIterator,
+    DepthFirstPrunedVisitor, DepthFirstVisitor, HierarchyDefect, HierarchyError, HierarchyObserver,
+    OrderIndex, Parent, RevChildrenIter, Tree, TreeBuilder, TreeBuilderClone, Visit,
 };
 
 /// A trait for modifying the worlds hierarchy. Implemented for `hecs::World`>
 pub trait HierarchyMut {
     /// Attach `child` to `parent`. Parent does not require an existing `Parent component`. Returns
-    /// the passed child.
+    /// the passed child. If `child` is already attached, it is detached from its previous parent
+    /// first.
     /// *Note*: The entity needs to be explicitly detached before being removed.
     fn attach<T: Component>(&mut self, child: Entity, parent: Entity) -> Result<Entity>;
 
+    /// Attach `child` to the front of `parent`'s children, making it the first child. Parent does
+    /// not require an existing `Parent component`. Returns the passed child.
+    fn attach_front<T: Component>(&mut self, child: Entity, parent: Entity) -> Result<Entity>;
+
+    /// Attach `child` immediately after `sibling` under the parent of `sibling`. Returns the passed
+    /// child. If `child` is already attached, it is detached from its previous parent first.
+    fn attach_after<T: Component>(&mut self, child: Entity, sibling: Entity) -> Result<Entity>;
+
+    /// Attach `child` immediately before `sibling` under the parent of `sibling`. Returns the
+    /// passed child. If `child` is already attached, it is detached from its previous parent
+    /// first.
+    fn attach_before<T: Component>(&mut self, child: Entity, sibling: Entity) -> Result<Entity>;
+
+    /// Attach `child` to the back of `parent` like [Self::attach], and give it an [OrderIndex]
+    /// one past that of its previous sibling.
+    fn attach_ordered<T: Component>(&mut self, child: Entity, parent: Entity) -> Result<Entity>;
+
+    /// Attach `child` to `parent` in the slot given by its [OrderIndex], i.e. before the first
+    /// sibling with a greater or missing index. Since [Self::detach] leaves the [OrderIndex] in
+    /// place, a child detached from and re-attached to the same parent lands in its original
+    /// position. Children without an [OrderIndex] are attached to the back.
+    fn attach_keep_order<T: Component>(&mut self, child: Entity, parent: Entity) -> Result<Entity>;
+
+    /// Attach all `children` to the back of `parent`, preserving the iteration order. Parent does
+    /// not require an existing `Parent component`. Fails without modifying the world if a child is
+    /// listed more than once, or if `parent` or one of its ancestors is among `children`.
+    fn attach_all<T: Component>(
+        &mut self,
+        parent: Entity,
+        children: impl IntoIterator<Item = Entity>,
+    ) -> Result<()>;
+
     /// Attach a new entity with specified components to `parent`. Parent does not require an existing `Parent component`. Returns
     /// the passed child.
     fn attach_new<T: Component, C: DynamicBundle>(
@@ -23,22 +66,190 @@ pub trait HierarchyMut {
         components: C,
     ) -> Result<Entity>;
 
-    /// Detaches all children from entity and detaches entity from parent. Use this before removing
-    /// entities to ensure no loose entity ids.
+    /// Spawns the tree of `builder` and attaches its root to `parent`. Parent does not require an
+    /// existing `Parent component`. Returns the new root.
+    fn attach_tree<T: Component>(
+        &mut self,
+        parent: Entity,
+        builder: TreeBuilder<T>,
+    ) -> Result<Entity>;
+
+    /// Detaches all children from entity and detaches entity from parent, if any. Use this before
+    /// removing entities to ensure no loose entity ids.
     fn detach_all<T: Component>(&mut self, entity: Entity) -> Result<()>;
 
     /// Detaches all children of parent.
     fn detach_children<T: Component>(&mut self, parent: Entity) -> Result<Vec<Entity>>;
+
+    /// Despawns all children of parent recursively, leaving parent in place.
     fn despawn_children<T: Component>(&mut self, parent: Entity) -> Result<()>;
 
+    /// Invokes `f` for each child of `parent` along with a command buffer for deferring
+    /// mutations. The command buffer is executed after all children have been visited.
+    fn for_each_child<T: Component, F: FnMut(Entity, &mut CommandBuffer)>(
+        &mut self,
+        parent: Entity,
+        f: F,
+    );
+
     /// Detach the child from tree `T`. The children of `child` will not remain in hierachy, but will
     /// remain attached to `child`, which means a later attach also will attach the children of `child`
-    /// into the hierarchy. Essentially moving the subtree.
-    fn detach<T: Component>(&mut self, child: Entity) -> Result<()>;
+    /// into the hierarchy. Essentially moving the subtree. Returns the former parent of `child`, or
+    /// `None` if `child` was not attached, in which case nothing is done.
+    ///
+    /// The `Child` component is removed from `child`, which makes it the root of its own subtree.
+    fn detach<T: Component>(&mut self, child: Entity) -> Result<Option<Entity>>;
+
+    /// Like [Self::attach], but notifies `observer` of the attachment, and of the detachment from
+    /// the previous parent if `child` was already attached.
+    fn attach_with_observer<T: Component, O: HierarchyObserver>(
+        &mut self,
+        child: Entity,
+        parent: Entity,
+        observer: &mut O,
+    ) -> Result<Entity>;
+
+    /// Like [Self::detach], but notifies `observer` if `child` was detached from a parent.
+    fn detach_with_observer<T: Component, O: HierarchyObserver>(
+        &mut self,
+        child: Entity,
+        observer: &mut O,
+    ) -> Result<Option<Entity>>;
+
+    /// Detach the child from tree `T`, handing its children over to its former parent. The
+    /// children take the place of `child` among its siblings. Returns the former parent of `child`.
+    fn detach_promote_children<T: Component>(&mut self, child: Entity) -> Result<Entity>;
 
     /// Despawn parent and all children recursively. Essentially despawns a whole subtree including
-    /// root. Does not fail if there are invalid, dangling IDs in tree.
-    fn despawn_all<T: Component>(&mut self, parent: Entity);
+    /// root. Does not fail if there are invalid, dangling IDs in tree. Returns the number of
+    /// despawned entities, including the root.
+    fn despawn_all<T: Component>(&mut self, parent: Entity) -> usize;
+
+    /// Despawns every tree of marker `T` in the world, starting from each root. Returns the
+    /// number of despawned entities. Other trees are left untouched, unless they share entities
+    /// with a tree of `T`.
+    fn despawn_all_roots<T: Component>(&mut self) -> usize;
+
+    /// Moves `child` to `new_index` among its current siblings. The parent is left unchanged.
+    /// An index past the last child moves `child` to the back.
+    fn move_child<T: Component>(&mut self, child: Entity, new_index: usize) -> Result<()>;
+
+    /// Moves `child` to the front of its current siblings, making it the first child.
+    fn move_to_front<T: Component>(&mut self, child: Entity) -> Result<()>;
+
+    /// Moves `child` to the back of its current siblings, making it the last child.
+    fn move_to_back<T: Component>(&mut self, child: Entity) -> Result<()>;
+
+    /// Swaps `child` with its previous sibling. Does nothing if `child` is the first child.
+    fn move_up<T: Component>(&mut self, child: Entity) -> Result<()>;
+
+    /// Swaps `child` with its next sibling. Does nothing if `child` is the last child.
+    fn move_down<T: Component>(&mut self, child: Entity) -> Result<()>;
+
+    /// Cyclically shifts the children of `parent` by `by` positions, so that the child at index
+    /// `by` becomes the first child. A negative `by` rotates the other way.
+    fn rotate_children<T: Component>(&mut self, parent: Entity, by: isize) -> Result<()>;
+
+    /// Sorts the children of `parent` with the comparator `cmp`. The sort is stable, so children
+    /// which compare equal keep their relative order. Children with an [OrderIndex] have it
+    /// rewritten to their new position.
+    fn sort_children_by<T: Component, F: FnMut(Entity, Entity) -> Ordering>(
+        &mut self,
+        parent: Entity,
+        cmp: F,
+    ) -> Result<()>;
+
+    /// Sorts the children of every parent in the subtree of `root`, including `root`, by the key
+    /// given by `key_fn`. The sort is stable, and children with an [OrderIndex] have it rewritten
+    /// to their new position, like [Self::sort_children_by].
+    fn sort_subtree_by_key<T: Component, K: Ord, F: FnMut(&World, Entity) -> K>(
+        &mut self,
+        root: Entity,
+        key_fn: F,
+    ) -> Result<()>;
+
+    /// Sorts the children of every parent in the subtree of `root` by their [OrderIndex]. Children
+    /// without an index are placed last, keeping their relative order.
+    fn restore_order<T: Component>(&mut self, root: Entity) -> Result<()>;
+
+    /// Swaps the positions of the two siblings `a` and `b`. Fails if they do not share the same
+    /// parent.
+    fn swap_siblings<T: Component>(&mut self, a: Entity, b: Entity) -> Result<()>;
+
+    /// Duplicates the subtree of `root`, including `root`. The components of each entity are
+    /// provided by `clone`. The new subtree keeps the sibling order of the original and is not
+    /// attached to any parent. Returns the new root.
+    fn clone_subtree<T: Component, F: FnMut(&World, Entity) -> EntityBuilderClone>(
+        &mut self,
+        root: Entity,
+        clone: F,
+    ) -> Result<Entity>;
+
+    /// Captures the subtree of `root`, including `root`, into a [TreeBuilderClone] like
+    /// [Hierarchy::capture_subtree] and then despawns it. The components of each entity are
+    /// provided by `extract`. Spawning the returned builder recreates the subtree.
+    fn extract_subtree<T: Component, F: Fn(&World, Entity) -> EntityBuilderClone>(
+        &mut self,
+        root: Entity,
+        extract: F,
+    ) -> TreeBuilderClone<T>;
+
+    /// Moves `child`, along with its subtree, to `index` among the children of `new_parent`.
+    /// An index past the last child appends. Fails if `new_parent` is `child` or one of its
+    /// descendants.
+    fn reparent<T: Component>(
+        &mut self,
+        child: Entity,
+        new_parent: Entity,
+        index: usize,
+    ) -> Result<()>;
+
+    /// Puts `new` in the place of `old` among its siblings and detaches `old`. The children of
+    /// `old` remain attached to `old`. `new` is detached from its current parent, if any. Fails
+    /// without modifying the world if `old` is not a child, or if `new` is an ancestor of `old`.
+    fn replace_child<T: Component>(&mut self, old: Entity, new: Entity) -> Result<()>;
+
+    /// Moves the subtree of `root` from tree `T` to tree `U`, keeping the sibling order. `root` is
+    /// detached from its parent in `T`, and becomes a root in `U`.
+    fn retag_subtree<T: Component, U: Component>(&mut self, root: Entity) -> Result<()>;
+
+    /// Attaches the free standing tree of `donor_root` to the back of `onto_parent`'s children.
+    /// The links below `donor_root` are left intact. Fails if `donor_root` is not a root, or if
+    /// `onto_parent` belongs to the tree of `donor_root`.
+    fn graft<T: Component>(&mut self, donor_root: Entity, onto_parent: Entity) -> Result<()>;
+
+    /// Recomputes `num_children`, `first_child`, `last_child` and the cached subtree size of every
+    /// parent in the subtree of `root` by walking the actual links. Children which can no longer
+    /// be reached, such as despawned entities, are dropped from the lists. Does nothing to a
+    /// healthy tree.
+    fn repair<T: Component>(&mut self, root: Entity) -> Result<()>;
+
+    /// Resolves component `C` down the subtree of `root`. The value of each descendant is replaced
+    /// by `combine` of the resolved value of its closest ancestor with a `C`, and its own value.
+    /// Descendants without a `C` are skipped.
+    fn propagate<T: Component, C: Component + Clone, F: Fn(&C, &C) -> C>(
+        &mut self,
+        root: Entity,
+        combine: F,
+    );
+
+    /// Removes every `Parent` and `Child` component of tree `T` from the world, flattening the
+    /// hierarchy. The entities themselves and other trees are left untouched.
+    fn clear_tree<T: Component>(&mut self);
+
+    /// Links already spawned entities according to `adjacency`, which maps each parent to its
+    /// children in order, such as a snapshot from [Hierarchy::to_adjacency]. The children are
+    /// appended to any existing children, and detached from any previous parent first. Fails
+    /// without modifying the world if an entity does not exist, a child is listed more than once
+    /// or the links would form a cycle.
+    fn load_adjacency<T: Component>(
+        &mut self,
+        adjacency: &HashMap<Entity, Vec<Entity>>,
+    ) -> Result<()>;
+
+    /// Borrows the world as a [Tree] for marker `T`, which forwards the hierarchy functions
+    /// without the need to name the marker.
+    fn tree<T: Component>(&mut self) -> Tree<'_, T>;
 }
 
 /// Non mutating part of hierarchy
@@ -49,74 +260,405 @@ where
     /// Returns the parent entity of child.
     fn parent<T: Component>(&self, child: Entity) -> Result<Entity>;
 
+    /// Returns the parent entity of child, or `None` if child is a root or not attached.
+    fn try_parent<T: Component>(&self, child: Entity) -> Option<Entity>;
+
+    /// Returns the ancestor `n` levels above `child`, where 1 is the immediate parent and 0 is
+    /// `child` itself. Returns `None` if the chain of ancestors is shorter than `n`.
+    fn nth_ancestor<T: Component>(&self, child: Entity, n: usize) -> Option<Entity>;
+
+    /// Returns the root of the tree `child` belongs to, which is `child` itself if it has no
+    /// parent. Fails if the ancestors of `child` form a cycle.
     fn root<T: Component>(&self, child: Entity) -> Result<Entity>;
 
+    /// Maps each of `entities` to the root of its tree, like [Self::root] but sharing a single
+    /// query for the whole batch. Entities which are not children map to themselves. If the
+    /// ancestors of an entity form a cycle, the traversal stops at the first repeated ancestor.
+    fn roots_of<T: Component>(&self, entities: &[Entity]) -> Vec<Entity>;
+
     /// Traverses the immediate children of parent. If parent is not a Parent, an empty iterator is
     /// returned.
-    fn children<T: Component>(&self, parent: Entity) -> ChildrenIter<T>;
+    fn children<T: Component>(&self, parent: Entity) -> ChildrenIter<'_, T>;
+
+    /// Traverses the immediate children of parent like [Self::children], yielding only the
+    /// children which satisfy `Q` along with the query data. Each child is looked up once in
+    /// `view`, see [Self::descendants_with].
+    fn children_with<'q, T: Component, Q: Query + QueryShared>(
+        &self,
+        parent: Entity,
+        view: &'q View<'_, Q>,
+    ) -> impl Iterator<Item = (Entity, Q::Item<'q>)>;
+
+    /// Traverses the immediate children of parent in reverse order, starting with the last child.
+    /// If parent is not a Parent, an empty iterator is returned.
+    fn children_rev<T: Component>(&self, parent: Entity) -> RevChildrenIter<'_, T>;
+
+    /// Traverses the other children of `entity`'s parent, excluding `entity` itself. If `entity` is
+    /// a root, an empty iterator is returned.
+    fn siblings<T: Component>(&self, entity: Entity) -> impl Iterator<Item = Entity> + '_;
+
+    /// Returns the zero based position of `child` among the children of its parent. Fails if
+    /// `child` is a root.
+    fn child_index<T: Component>(&self, child: Entity) -> Result<usize>;
+
+    /// Returns the first immediate child of `parent` which satisfies `pred`.
+    fn find_child<T: Component, F: Fn(&Self, Entity) -> bool>(
+        &self,
+        parent: Entity,
+        pred: F,
+    ) -> Option<Entity>;
+
+    /// Returns the first descendant of `root` in depth first order which satisfies `pred`. The
+    /// search stops at the first match, and no hierarchy components are borrowed while `pred` is
+    /// called.
+    fn find_descendant<T: Component, F: Fn(&Self, Entity) -> bool>(
+        &self,
+        root: Entity,
+        pred: F,
+    ) -> Option<Entity>;
 
     /// Traverse the tree upwards. Iterator does not include the child itself.
-    fn ancestors<T: Component>(&self, child: Entity) -> AncestorIter<T>;
+    fn ancestors<T: Component>(&self, child: Entity) -> AncestorIter<'_, T>;
+
+    /// Traverse the tree upwards, starting with `entity` itself.
+    fn ancestors_inclusive<T: Component>(
+        &self,
+        entity: Entity,
+    ) -> iter::Chain<iter::Once<Entity>, AncestorIter<'_, T>>;
+
+    /// Traverse the tree downwards from the root to the immediate parent of `child`. The reverse
+    /// of [Self::ancestors], and does not include the child itself.
+    fn ancestors_rev<T: Component>(&self, child: Entity) -> impl Iterator<Item = Entity>;
+
+    /// Returns the chain of entities from the root of the tree down to and including `entity`.
+    /// Stops at the first repeated entity if the hierarchy contains a cycle.
+    fn path_from_root<T: Component>(&self, entity: Entity) -> Vec<Entity>;
+
+    /// Returns the number of edges between `a` and `b` through their lowest common ancestor, or
+    /// `None` if they are not in the same tree. When one is an ancestor of the other this is the
+    /// difference in depth.
+    fn distance<T: Component>(&self, a: Entity, b: Entity) -> Option<usize>;
 
     /// Traverse the tree depth first. Iterator does not include the child itself.
-    fn descendants_depth_first<T: Component>(&self, root: Entity) -> DepthFirstIterator<T>;
+    fn descendants_depth_first<T: Component>(
+        &self,
+        root: Entity,
+    ) -> DepthFirstIterator<'_, Self, T>;
+
+    /// Traverse the tree depth first like [Self::descendants_depth_first], yielding only the
+    /// descendants which satisfy `Q` along with the query data. Each descendant is looked up once
+    /// in `view`, which is borrowed from a query by the caller so that the data can outlive the
+    /// iterator.
+    ///
+    /// # Example
+    /// ```rust
+    /// use hecs_hierarchy::*;
+    /// use hecs::*;
+    ///
+    /// struct Marker;
+    /// let mut world = World::default();
+    /// let root = world.spawn(("root",));
+    /// world.attach_new::<Marker, _>(root, ("a", 1_u32)).unwrap();
+    /// world.attach_new::<Marker, _>(root, ("b",)).unwrap();
+    ///
+    /// let mut query = world.query::<&u32>();
+    /// let view = query.view();
+    /// let values = world
+    ///     .descendants_with::<Marker, _>(root, &view)
+    ///     .map(|(_, v)| *v)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(values, [1]);
+    /// ```
+    fn descendants_with<'q, T: Component, Q: Query + QueryShared>(
+        &self,
+        root: Entity,
+        view: &'q View<'_, Q>,
+    ) -> impl Iterator<Item = (Entity, Q::Item<'q>)>;
+
+    /// Traverse the tree depth first like [Self::descendants_depth_first], but without descending
+    /// past `max_depth` levels. The immediate children of `root` are at level 1, so a `max_depth`
+    /// of 1 yields only the immediate children.
+    fn descendants_depth_first_limited<T: Component>(
+        &self,
+        root: Entity,
+        max_depth: usize,
+    ) -> DepthFirstIterator<'_, Self, T>;
+
+    /// Traverse the tree depth first, starting with `root` itself.
+    fn subtree<T: Component>(
+        &self,
+        root: Entity,
+    ) -> iter::Chain<iter::Once<Entity>, DepthFirstIterator<'_, Self, T>>;
+
+    /// Applies `f` to every descendant of `root` in depth first order and collects the results.
+    fn map_descendants<T: Component, B, F: Fn(&Self, Entity) -> B>(
+        &self,
+        root: Entity,
+        f: F,
+    ) -> Vec<B>;
+
+    /// Folds the subtree of `root` bottom up. Each node starts out with the value given by `init`,
+    /// which is then combined with the folded value of each of its children in order. Returns the
+    /// folded value of `root`.
+    fn fold_up<T: Component, A, I: Fn(Entity) -> A, F: Fn(A, A) -> A>(
+        &self,
+        root: Entity,
+        init: I,
+        combine: F,
+    ) -> A;
+
+    /// Traverse the subtree of `root` in post order, including `root` itself. Every node is
+    /// yielded after all of its children, and `root` is yielded last.
+    fn descendants_bottom_up<T: Component>(
+        &self,
+        root: Entity,
+    ) -> impl Iterator<Item = Entity> + '_;
+
+    /// Traverse the tree depth first, yielding only the descendants without children of their own.
+    fn leaves<T: Component>(&self, root: Entity) -> impl Iterator<Item = Entity> + '_;
 
     /// Traverse the tree depth first with an acceptance function
     fn visit<T: Component, F: Fn(&Self, Entity) -> bool + Component>(
         &self,
         root: Entity,
         accept: F,
-    ) -> DepthFirstVisitor<Self, T, F>;
+    ) -> DepthFirstVisitor<'_, Self, T, F>;
+
+    /// Traverse the tree depth first, letting `visit` decide whether each node is yielded,
+    /// skipped while still descending into its children, or pruned along with its subtree.
+    fn visit_pruned<T: Component, F: Fn(&Self, Entity) -> Visit + Component>(
+        &self,
+        root: Entity,
+        visit: F,
+    ) -> DepthFirstPrunedVisitor<'_, Self, T, F>;
 
     /// Traverse the tree breadth first. Iterator does not include the child itself.
     fn descendants_breadth_first<T: Component>(
         &self,
         root: Entity,
-    ) -> BreadthFirstIterator<Self, T>;
+    ) -> BreadthFirstIterator<'_, Self, T>;
+
+    /// Traverse the tree breadth first, yielding each node along with its level. The immediate
+    /// children of `root` are at level 1.
+    fn descendants_breadth_first_with_depth<T: Component>(
+        &self,
+        root: Entity,
+    ) -> BreadthFirstDepthIterator<'_, Self, T>;
+
+    /// Traverse the tree breadth first with an acceptance function. Nodes which are not accepted
+    /// are skipped along with their subtree.
+    fn visit_breadth_first<T: Component, F: Fn(&Self, Entity) -> bool + Component>(
+        &self,
+        root: Entity,
+        accept: F,
+    ) -> BreadthFirstVisitor<'_, Self, T, F>;
+
+    /// Returns the number of immediate children of `parent`, or 0 if `parent` is not a Parent.
+    fn child_count<T: Component>(&self, parent: Entity) -> usize;
+
+    /// Returns the number of siblings of `entity`, not including `entity` itself, or 0 if `entity`
+    /// is a root.
+    fn sibling_count<T: Component>(&self, entity: Entity) -> usize;
+
+    /// Returns true if `entity` is a Parent with at least one child.
+    fn has_children<T: Component>(&self, entity: Entity) -> bool;
+
+    /// Returns true if `entity` exists and has no children.
+    fn is_leaf<T: Component>(&self, entity: Entity) -> bool;
+
+    /// Returns true if `entity` exists and has no parent. Unlike [Self::roots], this includes
+    /// entities which are not attached to any tree, as they are the root of their own tree.
+    fn is_root<T: Component>(&self, entity: Entity) -> bool;
+
+    /// Captures the subtree of `root`, including `root`, into a [TreeBuilderClone] with the same
+    /// structure and sibling order. The components of each entity are provided by `extract`. The
+    /// builder can be cloned to spawn the subtree any number of times.
+    fn capture_subtree<T: Component, F: Fn(Entity) -> EntityBuilderClone>(
+        &self,
+        root: Entity,
+        extract: F,
+    ) -> TreeBuilderClone<T>;
+
+    /// Returns the number of descendants of `root`, not including `root` itself.
+    fn descendant_count<T: Component>(&self, root: Entity) -> usize;
+
+    /// Returns the number of edges on the longest path from `root` down to a leaf, which is 0 if
+    /// `root` has no children.
+    fn height<T: Component>(&self, root: Entity) -> usize;
+
+    /// Groups the descendants of `root` by level. The first group holds the immediate children,
+    /// the second the grandchildren, and so on.
+    fn levels<T: Component>(&self, root: Entity) -> Vec<Vec<Entity>>;
 
     /// Returns an iterator over all root objects in the world
-    fn roots<T: Component>(&self) -> Result<QueryBorrow<Without<&Parent<T>, &Child<T>>>>;
+    fn roots<T: Component>(&self) -> Result<QueryBorrow<'_, Without<&Parent<T>, &Child<T>>>>;
+
+    /// Returns the number of root entities in the world
+    fn root_count<T: Component>(&self) -> Result<usize>;
+
+    /// Returns all root entities in the world
+    fn root_entities<T: Component>(&self) -> Result<Vec<Entity>>;
+
+    /// Returns every entity of tree `T` in the world, that is every entity which is a parent, a
+    /// child, or both. Each entity is yielded once.
+    fn all_nodes<T: Component>(&self) -> impl Iterator<Item = Entity>;
+
+    /// Returns every `(child, parent)` pair of tree `T` in the world.
+    fn edges<T: Component>(&self) -> impl Iterator<Item = (Entity, Entity)>;
+
+    /// Returns a snapshot of the structure of the subtree of `root`, mapping each parent in the
+    /// subtree to its children in order. Entities without children are not included.
+    fn to_adjacency<T: Component>(&self, root: Entity) -> HashMap<Entity, Vec<Entity>>;
+
+    /// Formats the subtree of `root` as an indented tree, one node per line. The label of each
+    /// node is provided by `label`.
+    fn format_tree<T: Component, F: Fn(Entity) -> String>(&self, root: Entity, label: F) -> String;
+
+    /// Renders the subtree of `root` using box drawing characters, similar to the `tree`
+    /// command. The label of each node is provided by `label`.
+    fn render_tree<T: Component, F: Fn(Entity) -> String>(&self, root: Entity, label: F) -> String;
+
+    /// Checks the integrity of the subtree of `root`, including `root`. Every parent is checked
+    /// for a well formed list of children matching `num_children` and the cached subtree size.
+    /// Returns all found defects.
+    fn validate<T: Component>(&self, root: Entity)
+        -> std::result::Result<(), Vec<HierarchyDefect>>;
 }
 
 impl HierarchyMut for World {
     fn attach<T: Component>(&mut self, child: Entity, parent: Entity) -> Result<Entity> {
-        let mut maybe_p = self.try_get_mut::<Parent<T>>(parent);
-        if let Ok(ref mut p) = maybe_p {
-            p.num_children += 1;
-            let prev = p.last_child;
-            p.last_child = child;
+        attach_at::<T>(self, child, parent, usize::MAX)
+    }
 
-            if p.num_children == 1 {
-                mem::drop(maybe_p);
-                self.try_insert(child, (Child::<T>::new(parent, child, child),))?;
-            } else {
-                let mut prev_data = self.try_get_mut::<Child<T>>(prev)?;
-                let next = prev_data.next;
-                prev_data.next = child;
+    fn attach_front<T: Component>(&mut self, child: Entity, parent: Entity) -> Result<Entity> {
+        attach_at::<T>(self, child, parent, 0)
+    }
+
+    fn attach_after<T: Component>(&mut self, child: Entity, sibling: Entity) -> Result<Entity> {
+        attach_beside::<T>(self, child, sibling, true)
+    }
+
+    fn attach_before<T: Component>(&mut self, child: Entity, sibling: Entity) -> Result<Entity> {
+        attach_beside::<T>(self, child, sibling, false)
+    }
 
-                mem::drop(prev_data);
-                mem::drop(maybe_p);
+    fn attach_ordered<T: Component>(&mut self, child: Entity, parent: Entity) -> Result<Entity> {
+        attach_at::<T>(self, child, parent, usize::MAX)?;
 
-                // Update backward linking
-                {
-                    let mut next_data = self.try_get_mut::<Child<T>>(next)?;
-                    next_data.prev = child;
-                }
+        let prev = self.try_get::<Child<T>>(child)?.prev;
+        let index = if prev == child {
+            0
+        } else {
+            self.try_get::<OrderIndex<T>>(prev)
+                .map(|index| index.index() + 1)
+                .unwrap_or_default()
+        };
+
+        self.try_insert(child, (OrderIndex::<T>::new(index),))?;
+
+        Ok(child)
+    }
 
-                self.try_insert(child, (Child::<T>::new(parent, next, prev),))?;
+    fn attach_keep_order<T: Component>(&mut self, child: Entity, parent: Entity) -> Result<Entity> {
+        let index = self
+            .try_get::<OrderIndex<T>>(child)
+            .map(|index| index.index());
+        let index = match index {
+            Ok(index) => index,
+            Err(_) => return self.attach::<T>(child, parent),
+        };
+
+        // Detach first so that the child is not counted among its own siblings
+        if self.try_get::<Child<T>>(child).is_ok() {
+            self.detach::<T>(child)?;
+        }
+
+        let slot = self
+            .children::<T>(parent)
+            .position(|sibling| {
+                self.try_get::<OrderIndex<T>>(sibling)
+                    .map(|sibling| sibling.index() > index)
+                    .unwrap_or(true)
+            })
+            .unwrap_or(usize::MAX);
+
+        attach_at::<T>(self, child, parent, slot)
+    }
+
+    fn attach_all<T: Component>(
+        &mut self,
+        parent: Entity,
+        children: impl IntoIterator<Item = Entity>,
+    ) -> Result<()> {
+        let children = children.into_iter().collect::<Vec<_>>();
+        let (first, last) = match (children.first(), children.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return Ok(()),
+        };
+
+        // Validate everything before relinking, so that an error leaves the tree unchanged
+        let mut seen = HashSet::new();
+        for &child in &children {
+            if !seen.insert(child) {
+                return Err(HierarchyError::DuplicateChild(child));
             }
+        }
 
-            return Ok(child);
+        if seen.contains(&parent) || self.ancestors::<T>(parent).any(|val| seen.contains(&val)) {
+            return Err(HierarchyError::Cycle(parent));
         }
 
-        mem::drop(maybe_p);
+        // Detach from any previous parent
+        for &child in &children {
+            if self.try_get::<Child<T>>(child).is_ok() {
+                self.detach::<T>(child)?;
+            }
+        }
 
-        // Parent component didn't exist
-        self.try_insert(parent, (Parent::<T>::new(1, child),))?;
+        let existing = self
+            .try_get::<Parent<T>>(parent)
+            .map(|p| (p.num_children, p.first_child, p.last_child));
 
-        self.try_insert(child, (Child::<T>::new(parent, child, child),))?;
+        // The existing siblings to splice the run in between
+        let (prev, next) = match existing {
+            Ok((num_children, first_child, last_child)) if num_children > 0 => {
+                self.try_get_mut::<Child<T>>(last_child)?.next = first;
+                self.try_get_mut::<Child<T>>(first_child)?.prev = last;
+                (last_child, first_child)
+            }
+            Ok(_) => (last, first),
+            Err(_) => {
+                // Parent component didn't exist
+                self.try_insert(parent, (Parent::<T>::new(0, first, last),))?;
+                (last, first)
+            }
+        };
 
-        Ok(child)
+        for (i, &child) in children.iter().enumerate() {
+            let child_prev = if i == 0 { prev } else { children[i - 1] };
+            let child_next = children.get(i + 1).copied().unwrap_or(next);
+
+            self.try_insert(child, (Child::<T>::new(parent, child_next, child_prev),))?;
+        }
+
+        {
+            let mut p = self.try_get_mut::<Parent<T>>(parent)?;
+            if p.num_children == 0 {
+                p.first_child = first;
+            }
+            p.num_children += children.len();
+            p.last_child = last;
+        }
+
+        let added = children
+            .iter()
+            .map(|&child| subtree_len::<T>(self, child))
+            .sum::<usize>();
+
+        resize_subtree::<T>(self, parent, added as isize);
+
+        Ok(())
     }
 
     fn attach_new<T: Component, C: DynamicBundle>(
@@ -128,9 +670,23 @@ impl HierarchyMut for World {
         self.attach::<T>(child, parent)
     }
 
+    fn attach_tree<T: Component>(
+        &mut self,
+        parent: Entity,
+        mut builder: TreeBuilder<T>,
+    ) -> Result<Entity> {
+        let child = builder.spawn(self);
+        self.attach::<T>(child, parent)
+    }
+
     fn detach_all<T: Component>(&mut self, entity: Entity) -> Result<()> {
         self.detach_children::<T>(entity)?;
-        self.detach::<T>(entity)?;
+
+        // Roots have no parent to detach from
+        if self.try_get::<Child<T>>(entity).is_ok() {
+            self.detach::<T>(entity)?;
+        }
+
         Ok(())
     }
 
@@ -143,7 +699,11 @@ impl HierarchyMut for World {
             Ok(())
         })?;
 
-        self.remove_one::<Parent<T>>(parent).unwrap();
+        let removed = subtree_len::<T>(self, parent) - 1;
+        resize_subtree::<T>(self, parent, -(removed as isize));
+
+        // `parent` may never have had any children
+        let _ = self.remove_one::<Parent<T>>(parent);
 
         Ok(children)
     }
@@ -152,17 +712,37 @@ impl HierarchyMut for World {
     fn despawn_children<T: Component>(&mut self, parent: Entity) -> Result<()> {
         let children = self.children::<T>(parent).collect::<Vec<Entity>>();
 
-        children
-            .iter()
-            .for_each(|child| self.despawn_all::<Child<T>>(*child));
+        // Each child is detached before being despawned, which keeps the subtree sizes in sync
+        children.iter().for_each(|child| {
+            self.despawn_all::<T>(*child);
+        });
 
-        self.remove_one::<Parent<T>>(parent).unwrap();
+        // `parent` may never have had any children
+        let _ = self.remove_one::<Parent<T>>(parent);
 
         Ok(())
     }
 
-    fn detach<T: Component>(&mut self, child: Entity) -> Result<()> {
-        let data = self.try_get_mut::<Child<T>>(child)?;
+    fn for_each_child<T: Component, F: FnMut(Entity, &mut CommandBuffer)>(
+        &mut self,
+        parent: Entity,
+        mut f: F,
+    ) {
+        let children = self.children::<T>(parent).collect::<Vec<_>>();
+
+        let mut cmd = CommandBuffer::new();
+        children.into_iter().for_each(|child| f(child, &mut cmd));
+
+        cmd.execute(self);
+    }
+
+    fn detach<T: Component>(&mut self, child: Entity) -> Result<Option<Entity>> {
+        let data = match self.try_get_mut::<Child<T>>(child) {
+            Ok(data) => data,
+            // Nothing to detach
+            Err(hecs_schedule::Error::MissingComponent(_, _)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
         let parent = data.parent;
         let prev = data.prev;
         let next = data.next;
@@ -172,16 +752,106 @@ impl HierarchyMut for World {
         self.try_get_mut::<Child<T>>(prev)?.next = next;
         self.try_get_mut::<Child<T>>(next)?.prev = prev;
 
-        let mut parent = self.try_get_mut::<Parent<T>>(parent)?;
-        parent.num_children -= 1;
-        if parent.last_child == child {
-            parent.last_child = prev;
+        {
+            let mut parent = self.try_get_mut::<Parent<T>>(parent)?;
+            parent.num_children -= 1;
+            if parent.first_child == child {
+                parent.first_child = next;
+            }
+            if parent.last_child == child {
+                parent.last_child = prev;
+            }
         }
 
-        Ok(())
+        self.try_remove_one::<Child<T>>(child)?;
+
+        let removed = subtree_len::<T>(self, child);
+        resize_subtree::<T>(self, parent, -(removed as isize));
+
+        Ok(Some(parent))
+    }
+
+    fn attach_with_observer<T: Component, O: HierarchyObserver>(
+        &mut self,
+        child: Entity,
+        parent: Entity,
+        observer: &mut O,
+    ) -> Result<Entity> {
+        self.detach_with_observer::<T, O>(child, observer)?;
+        self.attach::<T>(child, parent)?;
+        observer.on_attach(child, parent);
+
+        Ok(child)
+    }
+
+    fn detach_with_observer<T: Component, O: HierarchyObserver>(
+        &mut self,
+        child: Entity,
+        observer: &mut O,
+    ) -> Result<Option<Entity>> {
+        let parent = self.detach::<T>(child)?;
+        if let Some(parent) = parent {
+            observer.on_detach(child, parent);
+        }
+
+        Ok(parent)
+    }
+
+    fn detach_promote_children<T: Component>(&mut self, child: Entity) -> Result<Entity> {
+        let children = self.children::<T>(child).collect::<Vec<_>>();
+
+        let (first, last) = match (children.first(), children.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => {
+                let parent = self
+                    .detach::<T>(child)?
+                    .ok_or(HierarchyError::NotAChild(child))?;
+                let _ = self.remove_one::<Parent<T>>(child);
+                return Ok(parent);
+            }
+        };
+
+        let (parent, prev, next) = {
+            let data = missing_as(
+                self.try_get::<Child<T>>(child),
+                HierarchyError::NotAChild(child),
+            )?;
+            (data.parent, data.prev, data.next)
+        };
+
+        for &e in &children {
+            self.try_get_mut::<Child<T>>(e)?.parent = parent;
+        }
+
+        // Splice the children in between the siblings of `child`
+        if prev != child {
+            self.try_get_mut::<Child<T>>(prev)?.next = first;
+            self.try_get_mut::<Child<T>>(first)?.prev = prev;
+            self.try_get_mut::<Child<T>>(last)?.next = next;
+            self.try_get_mut::<Child<T>>(next)?.prev = last;
+        }
+
+        {
+            let mut p = self.try_get_mut::<Parent<T>>(parent)?;
+            p.num_children += children.len() - 1;
+            if p.first_child == child {
+                p.first_child = first;
+            }
+            if p.last_child == child {
+                p.last_child = last;
+            }
+        }
+
+        self.try_remove_one::<Child<T>>(child)?;
+        self.try_remove_one::<Parent<T>>(child)?;
+
+        // Only `child` itself left the subtree of `parent`
+        resize_subtree::<T>(self, parent, -1);
+
+        Ok(parent)
     }
 
-    fn despawn_all<T: Component>(&mut self, parent: Entity) {
+    fn despawn_all<T: Component>(&mut self, parent: Entity) -> usize {
         let to_despawn = self
             .descendants_depth_first::<T>(parent)
             .collect::<Vec<_>>();
@@ -190,76 +860,1441 @@ impl HierarchyMut for World {
         let _ = self.detach::<T>(parent);
 
         // Should not panic since we just
-        to_despawn.iter().for_each(|entity| {
-            let _ = self.despawn(*entity);
-        });
+        let count = to_despawn
+            .iter()
+            .filter(|entity| self.despawn(**entity).is_ok())
+            .count();
 
-        let _ = self.despawn(parent);
+        count + self.despawn(parent).is_ok() as usize
     }
-}
 
-impl<W: GenericWorld> Hierarchy for W {
-    fn parent<T: Component>(&self, child: Entity) -> Result<Entity> {
-        self.try_get::<Child<T>>(child).map(|child| child.parent)
+    fn despawn_all_roots<T: Component>(&mut self) -> usize {
+        // Collect first as the roots can not be despawned while queried
+        let roots = self.root_entities::<T>().unwrap_or_default();
+
+        roots
+            .into_iter()
+            .map(|root| self.despawn_all::<T>(root))
+            .sum()
     }
 
-    fn root<T: Component>(&self, child: Entity) -> Result<Entity> {
-        let mut cur = child;
-        loop {
-            match self.parent::<T>(cur) {
-                Ok(val) => cur = val,
-                Err(hecs_schedule::Error::MissingComponent(_, _)) => break,
-                Err(val) => return Err(val),
-            }
+    fn move_child<T: Component>(&mut self, child: Entity, new_index: usize) -> Result<()> {
+        let parent = self.parent::<T>(child)?;
+
+        let index = self.children::<T>(parent).position(|val| val == child);
+        if index == Some(new_index) {
+            return Ok(());
         }
 
-        Ok(cur)
-    }
+        self.detach::<T>(child)?;
+        attach_at::<T>(self, child, parent, new_index)?;
 
-    fn children<T: Component>(&self, parent: Entity) -> ChildrenIter<T> {
-        self.try_get::<Parent<T>>(parent)
-            .and_then(|parent| {
-                let first_child = parent.first_child(self)?;
-
-                Ok(ChildrenIter::new(
-                    self,
-                    parent.num_children,
-                    Some(first_child),
-                ))
-            })
-            .unwrap_or_else(move |_| {
-                // Return an iterator that does nothing.
-                ChildrenIter::new(self, 0, None)
-            })
+        Ok(())
     }
 
-    fn ancestors<T: Component>(&self, child: Entity) -> AncestorIter<T> {
-        AncestorIter::new(self, child)
+    fn move_to_front<T: Component>(&mut self, child: Entity) -> Result<()> {
+        self.move_child::<T>(child, 0)
     }
 
-    fn descendants_depth_first<T: Component>(&self, root: Entity) -> DepthFirstIterator<T> {
-        DepthFirstIterator::new(self, root)
+    fn move_to_back<T: Component>(&mut self, child: Entity) -> Result<()> {
+        self.move_child::<T>(child, usize::MAX)
     }
 
-    /// Traverse the tree breadth first. Iterator does not include the child itself.
-    fn descendants_breadth_first<T: Component>(
-        &self,
-        root: Entity,
-    ) -> BreadthFirstIterator<Self, T> {
-        BreadthFirstIterator::new(self, root)
+    fn move_up<T: Component>(&mut self, child: Entity) -> Result<()> {
+        let (parent, prev) = {
+            let data = missing_as(
+                self.try_get::<Child<T>>(child),
+                HierarchyError::NotAChild(child),
+            )?;
+            (data.parent, data.prev)
+        };
+
+        if self.try_get::<Parent<T>>(parent)?.first_child == child {
+            return Ok(());
+        }
+
+        self.swap_siblings::<T>(prev, child)
     }
 
-    fn visit<T: Component, F: Fn(&Self, Entity) -> bool + Component>(
-        &self,
+    fn move_down<T: Component>(&mut self, child: Entity) -> Result<()> {
+        let (parent, next) = {
+            let data = missing_as(
+                self.try_get::<Child<T>>(child),
+                HierarchyError::NotAChild(child),
+            )?;
+            (data.parent, data.next)
+        };
+
+        if self.try_get::<Parent<T>>(parent)?.last_child == child {
+            return Ok(());
+        }
+
+        self.swap_siblings::<T>(child, next)
+    }
+
+    fn rotate_children<T: Component>(&mut self, parent: Entity, by: isize) -> Result<()> {
+        let (num_children, mut last_child) = {
+            let parent = missing_as(
+                self.try_get::<Parent<T>>(parent),
+                HierarchyError::NotAParent(parent),
+            )?;
+            (parent.num_children, parent.last_child)
+        };
+
+        if num_children == 0 {
+            return Ok(());
+        }
+
+        // The list is circular, so advancing `last_child` rotates the whole list
+        for _ in 0..by.rem_euclid(num_children as isize) {
+            last_child = self.try_get::<Child<T>>(last_child)?.next;
+        }
+
+        let first_child = self.try_get::<Child<T>>(last_child)?.next;
+
+        let mut parent = self.try_get_mut::<Parent<T>>(parent)?;
+        parent.first_child = first_child;
+        parent.last_child = last_child;
+
+        Ok(())
+    }
+
+    fn sort_children_by<T: Component, F: FnMut(Entity, Entity) -> Ordering>(
+        &mut self,
+        parent: Entity,
+        mut cmp: F,
+    ) -> Result<()> {
+        let mut children = self.children::<T>(parent).collect::<Vec<_>>();
+        children.sort_by(|&a, &b| cmp(a, b));
+
+        reorder_children::<T>(self, parent, &children)
+    }
+
+    fn sort_subtree_by_key<T: Component, K: Ord, F: FnMut(&World, Entity) -> K>(
+        &mut self,
+        root: Entity,
+        mut key_fn: F,
+    ) -> Result<()> {
+        let parents = self
+            .subtree::<T>(root)
+            .filter(|&e| self.try_get::<Parent<T>>(e).is_ok())
+            .collect::<Vec<_>>();
+
+        let world: &World = self;
+        for parent in parents {
+            let mut keyed = world
+                .children::<T>(parent)
+                .map(|child| (key_fn(world, child), child))
+                .collect::<Vec<_>>();
+            keyed.sort_by(|a, b| a.0.cmp(&b.0));
+
+            let children = keyed
+                .into_iter()
+                .map(|(_, child)| child)
+                .collect::<Vec<_>>();
+            reorder_children::<T>(world, parent, &children)?;
+        }
+
+        Ok(())
+    }
+
+    fn restore_order<T: Component>(&mut self, root: Entity) -> Result<()> {
+        let parents = self
+            .subtree::<T>(root)
+            .filter(|&e| self.try_get::<Parent<T>>(e).is_ok())
+            .collect::<Vec<_>>();
+
+        for parent in parents {
+            let mut children = self.children::<T>(parent).collect::<Vec<_>>();
+            children.sort_by_key(|&child| {
+                self.try_get::<OrderIndex<T>>(child)
+                    .map(|index| index.index())
+                    .unwrap_or(u32::MAX)
+            });
+
+            relink_children::<T>(self, parent, &children)?;
+        }
+
+        Ok(())
+    }
+
+    fn swap_siblings<T: Component>(&mut self, a: Entity, b: Entity) -> Result<()> {
+        let (parent, a_prev, a_next) = {
+            let data = missing_as(self.try_get::<Child<T>>(a), HierarchyError::NotAChild(a))?;
+            (data.parent, data.prev, data.next)
+        };
+
+        let (b_parent, b_prev, b_next) = {
+            let data = missing_as(self.try_get::<Child<T>>(b), HierarchyError::NotAChild(b))?;
+            (data.parent, data.prev, data.next)
+        };
+
+        if parent != b_parent {
+            return Err(HierarchyError::NotSiblings(a, b));
+        }
+
+        if a == b {
+            return Ok(());
+        }
+
+        if a_next == b && b_next == a {
+            // Only two children, the order is determined by `last_child` alone
+        } else if a_next == b {
+            // a_prev <-> a <-> b <-> b_next
+            self.try_get_mut::<Child<T>>(a_prev)?.next = b;
+            self.try_get_mut::<Child<T>>(b_next)?.prev = a;
+            relink::<T>(self, b, a_prev, a)?;
+            relink::<T>(self, a, b, b_next)?;
+        } else if b_next == a {
+            // b_prev <-> b <-> a <-> a_next
+            self.try_get_mut::<Child<T>>(b_prev)?.next = a;
+            self.try_get_mut::<Child<T>>(a_next)?.prev = b;
+            relink::<T>(self, a, b_prev, b)?;
+            relink::<T>(self, b, a, a_next)?;
+        } else {
+            self.try_get_mut::<Child<T>>(a_prev)?.next = b;
+            self.try_get_mut::<Child<T>>(a_next)?.prev = b;
+            self.try_get_mut::<Child<T>>(b_prev)?.next = a;
+            self.try_get_mut::<Child<T>>(b_next)?.prev = a;
+            relink::<T>(self, a, b_prev, b_next)?;
+            relink::<T>(self, b, a_prev, a_next)?;
+        }
+
+        let mut parent = self.try_get_mut::<Parent<T>>(parent)?;
+        if parent.first_child == a {
+            parent.first_child = b;
+        } else if parent.first_child == b {
+            parent.first_child = a;
+        }
+        if parent.last_child == a {
+            parent.last_child = b;
+        } else if parent.last_child == b {
+            parent.last_child = a;
+        }
+
+        Ok(())
+    }
+
+    fn clone_subtree<T: Component, F: FnMut(&World, Entity) -> EntityBuilderClone>(
+        &mut self,
+        root: Entity,
+        mut clone: F,
+    ) -> Result<Entity> {
+        fn clone_recursive<T: Component, F: FnMut(&World, Entity) -> EntityBuilderClone>(
+            world: &mut World,
+            entity: Entity,
+            clone: &mut F,
+        ) -> Result<Entity> {
+            let builder = clone(world, entity);
+            let children = world.children::<T>(entity).collect::<Vec<_>>();
+
+            let new = world.spawn(&builder.build());
+
+            for child in children {
+                let child = clone_recursive::<T, F>(world, child, clone)?;
+                world.attach::<T>(child, new)?;
+            }
+
+            Ok(new)
+        }
+
+        if !self.contains(root) {
+            return Err(HierarchyError::NoSuchEntity(root));
+        }
+
+        clone_recursive::<T, F>(self, root, &mut clone)
+    }
+
+    fn extract_subtree<T: Component, F: Fn(&World, Entity) -> EntityBuilderClone>(
+        &mut self,
+        root: Entity,
+        extract: F,
+    ) -> TreeBuilderClone<T> {
+        let world: &World = self;
+        let builder = world.capture_subtree::<T, _>(root, |e| extract(world, e));
+
+        self.despawn_all::<T>(root);
+
+        builder
+    }
+
+    fn reparent<T: Component>(
+        &mut self,
+        child: Entity,
+        new_parent: Entity,
+        index: usize,
+    ) -> Result<()> {
+        if child == new_parent || self.ancestors::<T>(new_parent).any(|val| val == child) {
+            return Err(HierarchyError::Cycle(new_parent));
+        }
+
+        attach_at::<T>(self, child, new_parent, index)?;
+
+        Ok(())
+    }
+
+    fn replace_child<T: Component>(&mut self, old: Entity, new: Entity) -> Result<()> {
+        if old == new {
+            return Ok(());
+        }
+
+        // Validate everything before detaching `new`, so that an error leaves the tree unchanged
+        missing_as(
+            self.try_get::<Child<T>>(old),
+            HierarchyError::NotAChild(old),
+        )?;
+
+        if !self.contains(new) {
+            return Err(HierarchyError::NoSuchEntity(new));
+        }
+
+        if self.ancestors::<T>(old).any(|val| val == new) {
+            return Err(HierarchyError::Cycle(new));
+        }
+
+        if self.try_get::<Child<T>>(new).is_ok() {
+            self.detach::<T>(new)?;
+        }
+
+        // `new` may have been a sibling of `old`, so read the links after detaching it
+        let (parent, prev, next) = {
+            let data = self.try_get::<Child<T>>(old)?;
+            (data.parent, data.prev, data.next)
+        };
+
+        if prev == old {
+            // `old` is the only child
+            self.try_insert(new, (Child::<T>::new(parent, new, new),))?;
+        } else {
+            self.try_get_mut::<Child<T>>(prev)?.next = new;
+            self.try_get_mut::<Child<T>>(next)?.prev = new;
+            self.try_insert(new, (Child::<T>::new(parent, next, prev),))?;
+        }
+
+        {
+            let mut p = self.try_get_mut::<Parent<T>>(parent)?;
+            if p.first_child == old {
+                p.first_child = new;
+            }
+            if p.last_child == old {
+                p.last_child = new;
+            }
+        }
+
+        self.try_remove_one::<Child<T>>(old)?;
+
+        let delta = subtree_len::<T>(self, new) as isize - subtree_len::<T>(self, old) as isize;
+        resize_subtree::<T>(self, parent, delta);
+
+        Ok(())
+    }
+
+    fn retag_subtree<T: Component, U: Component>(&mut self, root: Entity) -> Result<()> {
+        if self.try_get::<Child<T>>(root).is_ok() {
+            self.detach::<T>(root)?;
+        }
+
+        let parents = self
+            .subtree::<T>(root)
+            .filter(|&e| self.try_get::<Parent<T>>(e).is_ok())
+            .map(|e| (e, self.children::<T>(e).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+
+        for (parent, children) in parents {
+            for &child in &children {
+                self.try_remove_one::<Child<T>>(child)?;
+            }
+
+            self.try_remove_one::<Parent<T>>(parent)?;
+            self.attach_all::<U>(parent, children)?;
+        }
+
+        Ok(())
+    }
+
+    fn graft<T: Component>(&mut self, donor_root: Entity, onto_parent: Entity) -> Result<()> {
+        if self.try_get::<Child<T>>(donor_root).is_ok() {
+            return Err(HierarchyError::NotARoot(donor_root));
+        }
+
+        if self.root::<T>(onto_parent)? == donor_root {
+            return Err(HierarchyError::Cycle(onto_parent));
+        }
+
+        attach_at::<T>(self, donor_root, onto_parent, usize::MAX)?;
+
+        Ok(())
+    }
+
+    fn repair<T: Component>(&mut self, root: Entity) -> Result<()> {
+        repair_subtree::<T>(self, root, &mut HashSet::new())?;
+        Ok(())
+    }
+
+    fn propagate<T: Component, C: Component + Clone, F: Fn(&C, &C) -> C>(
+        &mut self,
+        root: Entity,
+        combine: F,
+    ) {
+        let value = self.try_get::<C>(root).ok().map(|c| (*c).clone());
+        let mut stack = vec![(root, value)];
+
+        while let Some((parent, value)) = stack.pop() {
+            let children = self.children::<T>(parent).collect::<Vec<_>>();
+
+            // Push in reverse to resolve the children in order
+            for child in children.into_iter().rev() {
+                let resolved = match self.try_get_mut::<C>(child) {
+                    Ok(mut local) => {
+                        if let Some(value) = &value {
+                            let resolved = combine(value, &*local);
+                            *local = resolved;
+                        }
+
+                        Some((*local).clone())
+                    }
+                    Err(_) => value.clone(),
+                };
+
+                stack.push((child, resolved));
+            }
+        }
+    }
+
+    fn clear_tree<T: Component>(&mut self) {
+        let parents = self
+            .query::<&Parent<T>>()
+            .iter()
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>();
+
+        let children = self
+            .query::<&Child<T>>()
+            .iter()
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>();
+
+        for e in parents {
+            let _ = self.remove_one::<Parent<T>>(e);
+        }
+
+        for e in children {
+            let _ = self.remove_one::<Child<T>>(e);
+        }
+    }
+
+    fn load_adjacency<T: Component>(
+        &mut self,
+        adjacency: &HashMap<Entity, Vec<Entity>>,
+    ) -> Result<()> {
+        let mut parents = HashMap::new();
+        for (&parent, children) in adjacency {
+            if let Some(&missing) = iter::once(&parent)
+                .chain(children)
+                .find(|&&e| !self.contains(e))
+            {
+                return Err(HierarchyError::NoSuchEntity(missing));
+            }
+
+            for &child in children {
+                if parents.insert(child, parent).is_some() {
+                    return Err(HierarchyError::DuplicateChild(child));
+                }
+            }
+        }
+
+        // Walk up from each parent using the new links where given, and the existing links
+        // otherwise
+        for &parent in adjacency.keys() {
+            let mut visited = HashSet::new();
+            let mut cur = Some(parent);
+            while let Some(entity) = cur {
+                if !visited.insert(entity) {
+                    return Err(HierarchyError::Cycle(entity));
+                }
+
+                cur = parents
+                    .get(&entity)
+                    .copied()
+                    .or_else(|| self.try_parent::<T>(entity));
+            }
+        }
+
+        // Detach every child up front, so that no intermediate state links an entity below its
+        // own descendant
+        for &child in parents.keys() {
+            if self.try_get::<Child<T>>(child).is_ok() {
+                self.detach::<T>(child)?;
+            }
+        }
+
+        for (&parent, children) in adjacency {
+            self.attach_all::<T>(parent, children.iter().copied())?;
+        }
+
+        Ok(())
+    }
+
+    fn tree<T: Component>(&mut self) -> Tree<'_, T> {
+        Tree::new(self)
+    }
+}
+
+impl<W: GenericWorld> Hierarchy for W {
+    fn parent<T: Component>(&self, child: Entity) -> Result<Entity> {
+        missing_as(
+            self.try_get::<Child<T>>(child).map(|data| data.parent),
+            HierarchyError::NotAChild(child),
+        )
+    }
+
+    fn try_parent<T: Component>(&self, child: Entity) -> Option<Entity> {
+        self.try_get::<Child<T>>(child).ok().map(|data| data.parent)
+    }
+
+    fn nth_ancestor<T: Component>(&self, child: Entity, n: usize) -> Option<Entity> {
+        (0..n).try_fold(child, |cur, _| self.try_parent::<T>(cur))
+    }
+
+    fn root<T: Component>(&self, child: Entity) -> Result<Entity> {
+        let mut visited = HashSet::new();
+        let mut cur = child;
+        loop {
+            if !visited.insert(cur) {
+                return Err(HierarchyError::Cycle(cur));
+            }
+
+            match self.parent::<T>(cur) {
+                Ok(val) => cur = val,
+                Err(HierarchyError::NotAChild(_)) => break,
+                Err(val) => return Err(val),
+            }
+        }
+
+        Ok(cur)
+    }
+
+    fn roots_of<T: Component>(&self, entities: &[Entity]) -> Vec<Entity> {
+        let mut query = match self.try_query::<&Child<T>>() {
+            Ok(query) => query,
+            Err(_) => return entities.to_vec(),
+        };
+        let view = query.view();
+
+        let mut visited = HashSet::new();
+        entities
+            .iter()
+            .map(|&entity| {
+                visited.clear();
+                let mut cur = entity;
+                while let Some(child) = view.get(cur) {
+                    if !visited.insert(cur) {
+                        break;
+                    }
+                    cur = child.parent;
+                }
+                cur
+            })
+            .collect()
+    }
+
+    fn children<T: Component>(&self, parent: Entity) -> ChildrenIter<'_, T> {
+        self.try_get::<Parent<T>>(parent)
+            .map(|parent| parent.children(self))
+            .unwrap_or_else(move |_| {
+                // Return an iterator that does nothing.
+                ChildrenIter::new(self, 0, None, None)
+            })
+    }
+
+    fn children_with<'q, T: Component, Q: Query + QueryShared>(
+        &self,
+        parent: Entity,
+        view: &'q View<'_, Q>,
+    ) -> impl Iterator<Item = (Entity, Q::Item<'q>)> {
+        self.children::<T>(parent)
+            .filter_map(move |child| view.get(child).map(|item| (child, item)))
+    }
+
+    fn children_rev<T: Component>(&self, parent: Entity) -> RevChildrenIter<'_, T> {
+        self.try_get::<Parent<T>>(parent)
+            .map(|parent| RevChildrenIter::new(self, parent.num_children, Some(parent.last_child)))
+            .unwrap_or_else(move |_| {
+                // Return an iterator that does nothing.
+                RevChildrenIter::new(self, 0, None)
+            })
+    }
+
+    fn siblings<T: Component>(&self, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.parent::<T>(entity)
+            .ok()
+            .into_iter()
+            .flat_map(move |parent| self.children::<T>(parent))
+            .filter(move |&sibling| sibling != entity)
+    }
+
+    fn child_index<T: Component>(&self, child: Entity) -> Result<usize> {
+        let parent = self.parent::<T>(child)?;
+
+        self.children::<T>(parent)
+            .position(|val| val == child)
+            .ok_or(HierarchyError::NotAChild(child))
+    }
+
+    fn find_child<T: Component, F: Fn(&Self, Entity) -> bool>(
+        &self,
+        parent: Entity,
+        pred: F,
+    ) -> Option<Entity> {
+        self.children::<T>(parent).find(|&child| pred(self, child))
+    }
+
+    fn find_descendant<T: Component, F: Fn(&Self, Entity) -> bool>(
+        &self,
+        root: Entity,
+        pred: F,
+    ) -> Option<Entity> {
+        let mut cursor = DepthFirstCursor::<T>::new(root);
+        while let Some(entity) = cursor.step(self) {
+            if pred(self, entity) {
+                return Some(entity);
+            }
+        }
+
+        None
+    }
+
+    fn ancestors<T: Component>(&self, child: Entity) -> AncestorIter<'_, T> {
+        AncestorIter::new(self, child)
+    }
+
+    fn ancestors_inclusive<T: Component>(
+        &self,
+        entity: Entity,
+    ) -> iter::Chain<iter::Once<Entity>, AncestorIter<'_, T>> {
+        iter::once(entity).chain(self.ancestors::<T>(entity))
+    }
+
+    fn ancestors_rev<T: Component>(&self, child: Entity) -> impl Iterator<Item = Entity> {
+        self.ancestors::<T>(child)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+    }
+
+    fn path_from_root<T: Component>(&self, entity: Entity) -> Vec<Entity> {
+        let mut visited = HashSet::new();
+
+        let mut path = self
+            .ancestors_inclusive::<T>(entity)
+            .take_while(|&e| visited.insert(e))
+            .collect::<Vec<_>>();
+
+        path.reverse();
+        path
+    }
+
+    fn distance<T: Component>(&self, a: Entity, b: Entity) -> Option<usize> {
+        let up = self
+            .ancestors_inclusive::<T>(a)
+            .enumerate()
+            .map(|(i, e)| (e, i))
+            .collect::<HashMap<_, _>>();
+
+        self.ancestors_inclusive::<T>(b)
+            .enumerate()
+            .find_map(|(j, e)| up.get(&e).map(|i| i + j))
+    }
+
+    fn descendants_depth_first<T: Component>(
+        &self,
+        root: Entity,
+    ) -> DepthFirstIterator<'_, Self, T> {
+        DepthFirstIterator::new(self, root)
+    }
+
+    fn descendants_with<'q, T: Component, Q: Query + QueryShared>(
+        &self,
+        root: Entity,
+        view: &'q View<'_, Q>,
+    ) -> impl Iterator<Item = (Entity, Q::Item<'q>)> {
+        self.descendants_depth_first::<T>(root)
+            .filter_map(move |e| view.get(e).map(|item| (e, item)))
+    }
+
+    fn descendants_depth_first_limited<T: Component>(
+        &self,
+        root: Entity,
+        max_depth: usize,
+    ) -> DepthFirstIterator<'_, Self, T> {
+        DepthFirstIterator::new_limited(self, root, max_depth)
+    }
+
+    fn subtree<T: Component>(
+        &self,
+        root: Entity,
+    ) -> iter::Chain<iter::Once<Entity>, DepthFirstIterator<'_, Self, T>> {
+        iter::once(root).chain(self.descendants_depth_first::<T>(root))
+    }
+
+    fn map_descendants<T: Component, B, F: Fn(&Self, Entity) -> B>(
+        &self,
+        root: Entity,
+        f: F,
+    ) -> Vec<B> {
+        self.descendants_depth_first::<T>(root)
+            .map(|e| f(self, e))
+            .collect()
+    }
+
+    fn fold_up<T: Component, A, I: Fn(Entity) -> A, F: Fn(A, A) -> A>(
+        &self,
+        root: Entity,
+        init: I,
+        combine: F,
+    ) -> A {
+        // The values of the nodes on the path from `root`, along with their remaining children
+        let mut values = vec![init(root)];
+        let mut stack = vec![self.children::<T>(root)];
+
+        while let Some(children) = stack.last_mut() {
+            if let Some(child) = children.next() {
+                values.push(init(child));
+                stack.push(self.children::<T>(child));
+            } else {
+                stack.pop();
+
+                // Fold the finished node into its parent
+                if values.len() > 1 {
+                    let value = values.pop().unwrap();
+                    let parent = values.pop().unwrap();
+                    values.push(combine(parent, value));
+                }
+            }
+        }
+
+        values.pop().unwrap()
+    }
+
+    fn descendants_bottom_up<T: Component>(
+        &self,
+        root: Entity,
+    ) -> impl Iterator<Item = Entity> + '_ {
+        // The nodes on the path from `root`, along with their remaining children
+        let mut stack = vec![(root, self.children::<T>(root))];
+
+        iter::from_fn(move || loop {
+            let (entity, children) = stack.last_mut()?;
+            match children.next() {
+                Some(child) => {
+                    let children = self.children::<T>(child);
+                    stack.push((child, children));
+                }
+                None => {
+                    // All children are yielded, so the node itself is next
+                    let entity = *entity;
+                    stack.pop();
+                    return Some(entity);
+                }
+            }
+        })
+    }
+
+    fn leaves<T: Component>(&self, root: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.descendants_depth_first::<T>(root).filter(move |&e| {
+            self.try_get::<Parent<T>>(e)
+                .map(|parent| parent.num_children == 0)
+                .unwrap_or(true)
+        })
+    }
+
+    /// Traverse the tree breadth first. Iterator does not include the child itself.
+    fn descendants_breadth_first<T: Component>(
+        &self,
+        root: Entity,
+    ) -> BreadthFirstIterator<'_, Self, T> {
+        BreadthFirstIterator::new(self, root)
+    }
+
+    fn visit<T: Component, F: Fn(&Self, Entity) -> bool + Component>(
+        &self,
         root: Entity,
         accept: F,
-    ) -> DepthFirstVisitor<Self, T, F> {
+    ) -> DepthFirstVisitor<'_, Self, T, F> {
         DepthFirstVisitor::new(self, root, accept)
     }
 
-    fn roots<T: Component>(&self) -> Result<QueryBorrow<Without<&Parent<T>, &Child<T>>>> {
+    fn visit_pruned<T: Component, F: Fn(&Self, Entity) -> Visit + Component>(
+        &self,
+        root: Entity,
+        visit: F,
+    ) -> DepthFirstPrunedVisitor<'_, Self, T, F> {
+        DepthFirstPrunedVisitor::new(self, root, visit)
+    }
+
+    fn descendants_breadth_first_with_depth<T: Component>(
+        &self,
+        root: Entity,
+    ) -> BreadthFirstDepthIterator<'_, Self, T> {
+        BreadthFirstDepthIterator::new(self, root)
+    }
+
+    fn visit_breadth_first<T: Component, F: Fn(&Self, Entity) -> bool + Component>(
+        &self,
+        root: Entity,
+        accept: F,
+    ) -> BreadthFirstVisitor<'_, Self, T, F> {
+        BreadthFirstVisitor::new(self, root, accept)
+    }
+
+    fn capture_subtree<T: Component, F: Fn(Entity) -> EntityBuilderClone>(
+        &self,
+        root: Entity,
+        extract: F,
+    ) -> TreeBuilderClone<T> {
+        fn capture_recursive<W: GenericWorld, T: Component, F: Fn(Entity) -> EntityBuilderClone>(
+            world: &W,
+            entity: Entity,
+            extract: &F,
+        ) -> TreeBuilderClone<T> {
+            let mut builder = TreeBuilderClone::new();
+            *builder.root_mut() = extract(entity);
+
+            for child in world.children::<T>(entity) {
+                builder.attach_tree(capture_recursive::<W, T, F>(world, child, extract));
+            }
+
+            builder
+        }
+
+        capture_recursive::<Self, T, F>(self, root, &extract)
+    }
+
+    fn child_count<T: Component>(&self, parent: Entity) -> usize {
+        self.try_get::<Parent<T>>(parent)
+            .map(|parent| parent.num_children)
+            .unwrap_or_default()
+    }
+
+    fn sibling_count<T: Component>(&self, entity: Entity) -> usize {
+        self.try_parent::<T>(entity)
+            .map(|parent| self.child_count::<T>(parent).saturating_sub(1))
+            .unwrap_or_default()
+    }
+
+    fn has_children<T: Component>(&self, entity: Entity) -> bool {
+        self.child_count::<T>(entity) > 0
+    }
+
+    fn is_leaf<T: Component>(&self, entity: Entity) -> bool {
+        match self.try_get::<Parent<T>>(entity) {
+            Ok(parent) => parent.num_children == 0,
+            Err(err) => matches!(err, hecs_schedule::Error::MissingComponent(_, _)),
+        }
+    }
+
+    fn is_root<T: Component>(&self, entity: Entity) -> bool {
+        matches!(
+            self.try_get::<Child<T>>(entity),
+            Err(hecs_schedule::Error::MissingComponent(_, _))
+        )
+    }
+
+    fn descendant_count<T: Component>(&self, root: Entity) -> usize {
+        self.try_get::<Parent<T>>(root)
+            .map(|parent| parent.subtree_size)
+            .unwrap_or_default()
+    }
+
+    fn height<T: Component>(&self, root: Entity) -> usize {
+        // Depth first only keeps the current path around, rather than a whole level
+        let mut descendants = self.descendants_depth_first::<T>(root);
+        iter::from_fn(|| descendants.next_with_depth())
+            .map(|(_, depth)| depth)
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn levels<T: Component>(&self, root: Entity) -> Vec<Vec<Entity>> {
+        let mut levels: Vec<Vec<Entity>> = Vec::new();
+
+        // Levels are visited in order, so a new level is always the next one
+        for (entity, depth) in self.descendants_breadth_first_with_depth::<T>(root) {
+            if depth > levels.len() {
+                levels.push(Vec::new());
+            }
+
+            levels[depth - 1].push(entity);
+        }
+
+        levels
+    }
+
+    fn roots<T: Component>(&self) -> Result<QueryBorrow<'_, Without<&Parent<T>, &Child<T>>>> {
         Ok(self.try_query::<&Parent<T>>()?.without::<&Child<T>>())
     }
+
+    fn root_count<T: Component>(&self) -> Result<usize> {
+        Ok(self.roots::<T>()?.iter().count())
+    }
+
+    fn root_entities<T: Component>(&self) -> Result<Vec<Entity>> {
+        Ok(self.roots::<T>()?.iter().map(|(e, _)| e).collect())
+    }
+
+    fn all_nodes<T: Component>(&self) -> impl Iterator<Item = Entity> {
+        let mut nodes = self
+            .try_query::<&Parent<T>>()
+            .map(|mut query| query.iter().map(|(e, _)| e).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        // Children which are also parents are already included
+        if let Ok(query) = self.try_query::<&Child<T>>() {
+            nodes.extend(query.without::<&Parent<T>>().iter().map(|(e, _)| e));
+        }
+
+        nodes.into_iter()
+    }
+
+    fn edges<T: Component>(&self) -> impl Iterator<Item = (Entity, Entity)> {
+        self.try_query::<&Child<T>>()
+            .map(|mut query| {
+                query
+                    .iter()
+                    .map(|(e, child)| (e, child.parent))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+            .into_iter()
+    }
+
+    fn to_adjacency<T: Component>(&self, root: Entity) -> HashMap<Entity, Vec<Entity>> {
+        let mut adjacency = HashMap::<_, Vec<_>>::new();
+
+        // Siblings are visited in order, so each list of children ends up ordered
+        for child in self.descendants_depth_first::<T>(root) {
+            if let Some(parent) = self.try_parent::<T>(child) {
+                adjacency.entry(parent).or_default().push(child);
+            }
+        }
+
+        adjacency
+    }
+
+    fn format_tree<T: Component, F: Fn(Entity) -> String>(&self, root: Entity, label: F) -> String {
+        let mut out = label(root);
+        out.push('\n');
+
+        let mut stack = self.children::<T>(root).map(|e| (e, 1)).collect::<Vec<_>>();
+        stack.reverse();
+
+        while let Some((entity, depth)) = stack.pop() {
+            let indent = " ".repeat((depth - 1) * 10);
+            writeln!(out, "{}|-------- {}", indent, label(entity)).unwrap();
+
+            // Push in reverse to visit the children in order
+            let start = stack.len();
+            stack.extend(self.children::<T>(entity).map(|e| (e, depth + 1)));
+            stack[start..].reverse();
+        }
+
+        out
+    }
+
+    fn render_tree<T: Component, F: Fn(Entity) -> String>(&self, root: Entity, label: F) -> String {
+        let mut out = label(root);
+        out.push('\n');
+
+        // Each entry holds the prefix drawn by the ancestors and whether the node is the last
+        // of its siblings
+        let mut stack = Vec::new();
+        let push_children = |stack: &mut Vec<(Entity, String, bool)>, parent, prefix: &str| {
+            let children = self.children::<T>(parent).collect::<Vec<_>>();
+            let len = children.len();
+            stack.extend(
+                children
+                    .into_iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(i, e)| (e, prefix.to_string(), i + 1 == len)),
+            );
+        };
+
+        push_children(&mut stack, root, "");
+
+        while let Some((entity, prefix, last)) = stack.pop() {
+            let branch = if last { "└── " } else { "├── " };
+            writeln!(out, "{}{}{}", prefix, branch, label(entity)).unwrap();
+
+            let bar = if last { "    " } else { "│   " };
+            push_children(&mut stack, entity, &(prefix + bar));
+        }
+
+        out
+    }
+
+    fn validate<T: Component>(
+        &self,
+        root: Entity,
+    ) -> std::result::Result<(), Vec<HierarchyDefect>> {
+        let mut defects = Vec::new();
+        validate_subtree::<T, _>(self, root, &mut HashSet::new(), &mut defects);
+
+        if defects.is_empty() {
+            Ok(())
+        } else {
+            Err(defects)
+        }
+    }
+}
+
+/// Validates the list of children of `parent` and recurses into the children. Returns the actual
+/// number of descendants.
+fn validate_subtree<T: Component, W: GenericWorld>(
+    world: &W,
+    parent: Entity,
+    visited: &mut HashSet<Entity>,
+    defects: &mut Vec<HierarchyDefect>,
+) -> usize {
+    let (num_children, first_child, last_child, subtree_size) =
+        match world.try_get::<Parent<T>>(parent) {
+            Ok(p) => (p.num_children, p.first_child, p.last_child, p.subtree_size),
+            Err(_) => return 0,
+        };
+
+    let mut children = Vec::new();
+
+    if num_children > 0 {
+        if let Ok(last) = world.try_get::<Child<T>>(last_child) {
+            if last.next != first_child {
+                defects.push(HierarchyDefect::FirstChildMismatch {
+                    parent,
+                    first_child,
+                    expected: last.next,
+                });
+            }
+        }
+
+        let mut current = last_child;
+        // Walk the circular list once, starting and ending at `last_child`
+        loop {
+            let data = match world.try_get::<Child<T>>(current) {
+                Ok(data) => data,
+                Err(_) => {
+                    defects.push(HierarchyDefect::MissingChild {
+                        parent,
+                        child: current,
+                    });
+                    break;
+                }
+            };
+
+            if data.parent != parent {
+                defects.push(HierarchyDefect::WrongParent {
+                    parent,
+                    child: current,
+                    found: data.parent,
+                });
+            }
+
+            if let Ok(next) = world.try_get::<Child<T>>(data.next) {
+                if next.prev != current {
+                    defects.push(HierarchyDefect::BrokenLink {
+                        child: current,
+                        next: data.next,
+                    });
+                }
+            }
+
+            // Guard against cycles not passing through `last_child`
+            if !visited.insert(current) {
+                break;
+            }
+
+            children.push(current);
+            current = data.next;
+
+            if current == last_child {
+                break;
+            }
+        }
+    }
+
+    if children.len() != num_children {
+        defects.push(HierarchyDefect::ChildCountMismatch {
+            parent,
+            num_children,
+            actual: children.len(),
+        });
+    }
+
+    let actual = children
+        .iter()
+        .map(|&child| 1 + validate_subtree::<T, W>(world, child, visited, defects))
+        .sum();
+
+    if actual != subtree_size {
+        defects.push(HierarchyDefect::SubtreeSizeMismatch {
+            parent,
+            subtree_size,
+            actual,
+        });
+    }
+
+    actual
+}
+
+/// Relinks the reachable children of `parent` and recurses into the children. Returns the number
+/// of descendants.
+fn repair_subtree<T: Component>(
+    world: &World,
+    parent: Entity,
+    visited: &mut HashSet<Entity>,
+) -> Result<usize> {
+    let (num_children, first_child, last_child) = match world.try_get::<Parent<T>>(parent) {
+        Ok(p) => (p.num_children, p.first_child, p.last_child),
+        Err(_) => return Ok(0),
+    };
+
+    // An emptied parent keeps pointing at its former last child, which may since have been
+    // attached elsewhere
+    if num_children == 0 {
+        world.try_get_mut::<Parent<T>>(parent)?.subtree_size = 0;
+        return Ok(0);
+    }
+
+    // Only follow links of entities which are actually children of `parent`
+    let links = |e: Entity| {
+        world
+            .try_get::<Child<T>>(e)
+            .ok()
+            .filter(|c| c.parent == parent)
+            .map(|c| (c.prev, c.next))
+    };
+
+    let mut children = Vec::new();
+
+    if let Some((_, first_child)) = links(last_child) {
+        // Walk forward from the first child
+        let mut current = first_child;
+        while let Some((_, next)) = links(current) {
+            if !visited.insert(current) {
+                break;
+            }
+
+            children.push(current);
+            if current == last_child {
+                break;
+            }
+
+            current = next;
+        }
+
+        // The forward walk was cut short, recover the tail by walking backwards
+        if children.last() != Some(&last_child) {
+            let mut tail = Vec::new();
+            let mut current = last_child;
+            while let Some((prev, _)) = links(current) {
+                if !visited.insert(current) {
+                    break;
+                }
+
+                tail.push(current);
+                current = prev;
+            }
+
+            children.extend(tail.into_iter().rev());
+        }
+    } else if links(first_child).is_some() {
+        // The last child is stale, walk forward from the first child until the list wraps or
+        // breaks
+        let mut current = first_child;
+        while let Some((_, next)) = links(current) {
+            if !visited.insert(current) {
+                break;
+            }
+
+            children.push(current);
+            current = next;
+        }
+    } else {
+        // Neither end of the list can be reached, find the children by their parent instead
+        let found = world
+            .query::<&Child<T>>()
+            .iter()
+            .filter(|(e, c)| c.parent == parent && !visited.contains(e))
+            .map(|(e, c)| (e, c.prev))
+            .collect::<Vec<_>>();
+
+        let members = found.iter().map(|&(e, _)| e).collect::<HashSet<_>>();
+
+        // Follow the links from the start of each run first, to keep the original order
+        let starts = found
+            .iter()
+            .filter(|(_, prev)| !members.contains(prev))
+            .chain(&found);
+
+        for &(start, _) in starts {
+            let mut current = start;
+            while members.contains(&current) && visited.insert(current) {
+                children.push(current);
+                current = match links(current) {
+                    Some((_, next)) => next,
+                    None => break,
+                };
+            }
+        }
+    }
+
+    let len = children.len();
+    for (i, &child) in children.iter().enumerate() {
+        let mut data = world.try_get_mut::<Child<T>>(child)?;
+        data.prev = children[(i + len - 1) % len];
+        data.next = children[(i + 1) % len];
+    }
+
+    let mut subtree_size = 0;
+    for &child in &children {
+        subtree_size += 1 + repair_subtree::<T>(world, child, visited)?;
+    }
+
+    let mut p = world.try_get_mut::<Parent<T>>(parent)?;
+    p.num_children = len;
+    p.subtree_size = subtree_size;
+    if let (Some(&first), Some(&last)) = (children.first(), children.last()) {
+        p.first_child = first;
+        p.last_child = last;
+    }
+
+    Ok(subtree_size)
+}
+
+/// Links `child` into the children of `parent` so that it ends up at `index`. An `index` past
+/// the last child appends.
+fn attach_at<T: Component>(
+    world: &mut World,
+    child: Entity,
+    parent: Entity,
+    index: usize,
+) -> Result<Entity> {
+    // Detach from any previous parent
+    if world.try_get::<Child<T>>(child).is_ok() {
+        world.detach::<T>(child)?;
+    }
+
+    let existing = world
+        .try_get::<Parent<T>>(parent)
+        .map(|p| (p.num_children, p.last_child));
+
+    let (num_children, last_child) = match existing {
+        Ok(val) => val,
+        Err(_) => {
+            // Parent component didn't exist
+            world.try_insert(parent, (Parent::<T>::new(0, child, child),))?;
+            (0, child)
+        }
+    };
+
+    if num_children == 0 {
+        let mut p = world.try_get_mut::<Parent<T>>(parent)?;
+        p.num_children = 1;
+        p.first_child = child;
+        p.last_child = child;
+        mem::drop(p);
+
+        world.try_insert(child, (Child::<T>::new(parent, child, child),))?;
+        resize_subtree::<T>(world, parent, subtree_len::<T>(world, child) as isize);
+
+        return Ok(child);
+    }
+
+    // Find the siblings to link between
+    let (prev, next) = if index >= num_children {
+        (last_child, world.try_get::<Child<T>>(last_child)?.next)
+    } else {
+        let next = world
+            .children::<T>(parent)
+            .nth(index)
+            .ok_or(HierarchyError::NoSuchEntity(last_child))?;
+        (world.try_get::<Child<T>>(next)?.prev, next)
+    };
+
+    world.try_get_mut::<Child<T>>(prev)?.next = child;
+    world.try_get_mut::<Child<T>>(next)?.prev = child;
+
+    {
+        let mut p = world.try_get_mut::<Parent<T>>(parent)?;
+        p.num_children += 1;
+        if index == 0 {
+            p.first_child = child;
+        }
+        if index >= num_children {
+            p.last_child = child;
+        }
+    }
+
+    world.try_insert(child, (Child::<T>::new(parent, next, prev),))?;
+    resize_subtree::<T>(world, parent, subtree_len::<T>(world, child) as isize);
+
+    Ok(child)
+}
+
+/// Attaches `child` next to `sibling` under the parent of `sibling`, after `sibling` if `after`
+/// is set and before it otherwise.
+fn attach_beside<T: Component>(
+    world: &mut World,
+    child: Entity,
+    sibling: Entity,
+    after: bool,
+) -> Result<Entity> {
+    let parent = world.parent::<T>(sibling)?;
+
+    if child == sibling {
+        return Ok(child);
+    }
+
+    if child == parent || world.ancestors::<T>(parent).any(|val| val == child) {
+        return Err(HierarchyError::Cycle(parent));
+    }
+
+    // Detach from any previous parent, which may also be the parent of `sibling`
+    if world.try_get::<Child<T>>(child).is_ok() {
+        world.detach::<T>(child)?;
+    }
+
+    let (prev, next) = {
+        let data = world.try_get::<Child<T>>(sibling)?;
+        if after {
+            (sibling, data.next)
+        } else {
+            (data.prev, sibling)
+        }
+    };
+
+    world.try_get_mut::<Child<T>>(prev)?.next = child;
+    world.try_get_mut::<Child<T>>(next)?.prev = child;
+
+    {
+        let mut p = world.try_get_mut::<Parent<T>>(parent)?;
+        p.num_children += 1;
+        if after && p.last_child == sibling {
+            p.last_child = child;
+        }
+        if !after && p.first_child == sibling {
+            p.first_child = child;
+        }
+    }
+
+    world.try_insert(child, (Child::<T>::new(parent, next, prev),))?;
+    resize_subtree::<T>(world, parent, subtree_len::<T>(world, child) as isize);
+
+    Ok(child)
+}
+
+/// Returns the number of entities in the subtree of `entity`, including `entity` itself.
+fn subtree_len<T: Component>(world: &World, entity: Entity) -> usize {
+    1 + world
+        .try_get::<Parent<T>>(entity)
+        .map(|p| p.subtree_size)
+        .unwrap_or_default()
+}
+
+/// Links freshly spawned `children` under the freshly spawned `parent` in a single pass. When
+/// neither `parent` nor `children` are already part of the hierarchy, the detaching and splicing
+/// done by [HierarchyMut::attach_all] is skipped.
+pub(crate) fn link_new_children<T: Component>(
+    world: &mut World,
+    parent: Entity,
+    children: &[Entity],
+) -> Result<()> {
+    let (first, last) = match (children.first(), children.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Ok(()),
+    };
+
+    // Existing entities may already be linked
+    if world.try_get::<Parent<T>>(parent).is_ok()
+        || children
+            .iter()
+            .any(|&child| world.try_get::<Child<T>>(child).is_ok())
+    {
+        return world.attach_all::<T>(parent, children.iter().copied());
+    }
+
+    let len = children.len();
+    let mut subtree_size = 0;
+    for (i, &child) in children.iter().enumerate() {
+        subtree_size += subtree_len::<T>(world, child);
+        world.try_insert(
+            child,
+            (Child::<T>::new(
+                parent,
+                children[(i + 1) % len],
+                children[(i + len - 1) % len],
+            ),),
+        )?;
+    }
+
+    let mut p = Parent::<T>::new(len, first, last);
+    p.subtree_size = subtree_size;
+    world.try_insert(parent, (p,))
+}
+
+/// Adjusts the cached subtree size of `parent` and all its ancestors by `delta`.
+fn resize_subtree<T: Component>(world: &World, parent: Entity, delta: isize) {
+    let mut current = Some(parent);
+    while let Some(entity) = current {
+        if let Ok(mut p) = world.try_get_mut::<Parent<T>>(entity) {
+            p.subtree_size = (p.subtree_size as isize + delta) as usize;
+        }
+
+        current = world.try_get::<Child<T>>(entity).ok().map(|c| c.parent);
+    }
+}
+
+/// Links the existing children of `parent` in the order of `children`, and rewrites their
+/// [OrderIndex] to match
+fn reorder_children<T: Component>(
+    world: &World,
+    parent: Entity,
+    children: &[Entity],
+) -> Result<()> {
+    for (i, &child) in children.iter().enumerate() {
+        if let Ok(mut index) = world.try_get_mut::<OrderIndex<T>>(child) {
+            *index = OrderIndex::new(i as u32);
+        }
+    }
+
+    relink_children::<T>(world, parent, children)
+}
+
+/// Links the existing children of `parent` in the order of `children`
+fn relink_children<T: Component>(world: &World, parent: Entity, children: &[Entity]) -> Result<()> {
+    let len = children.len();
+    for (i, &child) in children.iter().enumerate() {
+        relink::<T>(
+            world,
+            child,
+            children[(i + len - 1) % len],
+            children[(i + 1) % len],
+        )?;
+    }
+
+    if let (Some(&first), Some(&last)) = (children.first(), children.last()) {
+        let mut p = world.try_get_mut::<Parent<T>>(parent)?;
+        p.first_child = first;
+        p.last_child = last;
+    }
+
+    Ok(())
+}
+
+/// Sets the sibling links of `child`
+fn relink<T: Component>(world: &World, child: Entity, prev: Entity, next: Entity) -> Result<()> {
+    let mut data = world.try_get_mut::<Child<T>>(child)?;
+    data.prev = prev;
+    data.next = next;
+    Ok(())
 }
 
 trait WorldExt {
@@ -270,14 +2305,22 @@ trait WorldExt {
 impl WorldExt for World {
     fn try_insert(&mut self, e: Entity, c: impl DynamicBundle) -> Result<()> {
         self.insert(e, c)
-            .map_err(|_| hecs_schedule::Error::NoSuchEntity(e))
+            .map_err(|_| HierarchyError::NoSuchEntity(e))
     }
 
     fn try_remove_one<C: Component>(&mut self, e: Entity) -> Result<C> {
         self.remove_one::<C>(e)
-            .map_err(|_| hecs_schedule::Error::NoSuchEntity(e))
+            .map_err(|_| HierarchyError::NoSuchEntity(e))
     }
 }
 
 /// A query for defininig a compatible subworld for [Hierarchy]
 pub type HierarchyQuery<'a, T> = (&'a Parent<T>, &'a Child<T>);
+
+/// Maps a missing component error of `result` to `err`, such as when an entity is not a child.
+fn missing_as<T>(result: hecs_schedule::error::Result<T>, err: HierarchyError) -> Result<T> {
+    result.map_err(|e| match e {
+        hecs_schedule::Error::MissingComponent(_, _) => err,
+        e => e.into(),
+    })
+}
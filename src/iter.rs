@@ -1,19 +1,26 @@
-use std::{collections::VecDeque, marker::PhantomData};
+use std::{collections::HashSet, marker::PhantomData};
 
 use hecs::{Component, Entity, QueryBorrow};
 use hecs_schedule::GenericWorld;
 use smallvec::{smallvec, SmallVec};
 
-use crate::{Child, Hierarchy, Parent};
+use crate::{smallvec_dequeue::SmallVecDequeue, Child, Hierarchy, Parent};
 
 const STACK_SIZE: usize = 64;
+const QUEUE_SIZE: usize = 32;
 
 /// Iterates children along with Query `Q`. Children who do not satisfy `Q` will be skipped.
-/// Count is known in advanced and will not fold iterator.
+/// Count and last are known in advance and will not fold the iterator, unless the last child is
+/// no longer linked. Since entities need to be detached before being removed, the cached
+/// `num_children` is trusted otherwise.
 pub struct ChildrenIter<'a, T: Component> {
     query: QueryBorrow<'a, &'a Child<T>>,
     remaining: usize,
     current: Option<Entity>,
+    /// The back cursor, which is the last child not yet yielded from either end
+    last: Option<Entity>,
+    /// The first child, where the circular list wraps around
+    first: Option<Entity>,
     marker: PhantomData<T>,
 }
 
@@ -22,14 +29,38 @@ impl<'a, T: Component> ChildrenIter<'a, T> {
         world: &'a W,
         num_children: usize,
         current: Option<Entity>,
+        last: Option<Entity>,
     ) -> Self {
         Self {
             query: world.try_query().unwrap(),
             remaining: num_children,
             current,
+            last,
+            first: current,
             marker: PhantomData,
         }
     }
+
+    /// Returns true if there are children left and the back cursor is still a child, in which
+    /// case the remaining children are known without walking the list
+    fn back_linked(&mut self) -> bool {
+        match self.last {
+            Some(last) if self.remaining > 0 => self.query.view().get(last).is_some(),
+            _ => false,
+        }
+    }
+}
+
+impl<'a, T: Component> std::fmt::Debug for ChildrenIter<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChildrenIter")
+            .field("marker", &std::any::type_name::<T>())
+            .field("remaining", &self.remaining)
+            .field("current", &self.current)
+            .field("last", &self.last)
+            .field("first", &self.first)
+            .finish()
+    }
 }
 
 impl<'a, T> Iterator for ChildrenIter<'a, T>
@@ -47,27 +78,170 @@ where
 
         let current = self.current?;
         let view = self.query.view();
-        let data = view.get(current)?;
+        let data = match view.get(current) {
+            Some(data) => data,
+            None => {
+                // The child was despawned or unlinked, which ends the chain of siblings
+                self.remaining = 0;
+                return None;
+            }
+        };
+
+        // Stop when meeting the back cursor or wrapping around, even if `num_children` claims
+        // there are more children
+        if Some(current) == self.last || Some(data.next) == self.first {
+            self.remaining = 0;
+        }
 
         self.current = Some(data.next);
         Some(current)
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.remaining, Some(self.remaining))
+        // `num_children` is only an upper bound, as the list may end early
+        (0, Some(self.remaining))
     }
 
-    fn count(self) -> usize
+    fn count(mut self) -> usize
+    where
+        Self: Sized,
+    {
+        if self.back_linked() {
+            self.remaining
+        } else {
+            self.fold(0, |count, _| count + 1)
+        }
+    }
+
+    fn last(mut self) -> Option<Self::Item>
     where
         Self: Sized,
     {
-        self.remaining
+        // The last child is known up front, so there is no need to walk the list
+        if self.back_linked() {
+            self.last
+        } else {
+            self.fold(None, |_, child| Some(child))
+        }
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for ChildrenIter<'a, T>
+where
+    T: Component,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // Both cursors share `remaining`, so they stop before passing each other
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+
+        let current = self.last?;
+        let view = self.query.view();
+        let data = match view.get(current) {
+            Some(data) => data,
+            None => {
+                // The child was despawned or unlinked, which ends the chain of siblings
+                self.remaining = 0;
+                return None;
+            }
+        };
+
+        // Stop when meeting the front cursor or wrapping around
+        if Some(current) == self.current || Some(current) == self.first {
+            self.remaining = 0;
+        }
+
+        self.last = Some(data.prev);
+        Some(current)
+    }
+}
+
+/// Iterates children in reverse order, starting at the last child and following the `prev`
+/// links.
+pub struct RevChildrenIter<'a, T: Component> {
+    query: QueryBorrow<'a, &'a Child<T>>,
+    remaining: usize,
+    current: Option<Entity>,
+    /// The last child, where the circular list wraps around
+    last: Option<Entity>,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: Component> RevChildrenIter<'a, T> {
+    pub(crate) fn new<W: GenericWorld>(
+        world: &'a W,
+        num_children: usize,
+        current: Option<Entity>,
+    ) -> Self {
+        Self {
+            query: world.try_query().unwrap(),
+            remaining: num_children,
+            current,
+            last: current,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Component> std::fmt::Debug for RevChildrenIter<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RevChildrenIter")
+            .field("marker", &std::any::type_name::<T>())
+            .field("remaining", &self.remaining)
+            .field("current", &self.current)
+            .field("last", &self.last)
+            .finish()
+    }
+}
+
+impl<'a, T> Iterator for RevChildrenIter<'a, T>
+where
+    T: Component,
+{
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+
+        let current = self.current?;
+        let view = self.query.view();
+        let data = match view.get(current) {
+            Some(data) => data,
+            None => {
+                // The child was despawned or unlinked, which ends the chain of siblings
+                self.remaining = 0;
+                return None;
+            }
+        };
+
+        // Stop when wrapping around, even if `num_children` claims there are more children
+        if Some(data.prev) == self.last {
+            self.remaining = 0;
+        }
+
+        self.current = Some(data.prev);
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // `num_children` is only an upper bound, as the list may end early
+        (0, Some(self.remaining))
     }
 }
 
+/// Iterates the ancestors of an entity. Stops at the first repeated entity if the hierarchy
+/// contains a cycle.
 pub struct AncestorIter<'a, T: Component> {
     query: QueryBorrow<'a, &'a Child<T>>,
     current: Entity,
+    visited: HashSet<Entity>,
     marker: PhantomData<T>,
 }
 
@@ -76,6 +250,7 @@ impl<'a, T: Component> AncestorIter<'a, T> {
         Self {
             query: world.try_query().unwrap(),
             current,
+            visited: HashSet::new(),
             marker: PhantomData,
         }
     }
@@ -85,50 +260,225 @@ impl<'a, T: Component> Iterator for AncestorIter<'a, T> {
     type Item = Entity;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(child) = self.query.view().get(self.current) {
-            self.current = child.parent;
-            Some(child.parent)
-        } else {
-            None
+        let parent = self.query.view().get(self.current)?.parent;
+
+        // Guard against cycles
+        self.visited.insert(self.current);
+        if self.visited.contains(&parent) {
+            return None;
         }
+
+        self.current = parent;
+        Some(parent)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct StackFrame {
+    /// The parent whose children are traversed
+    parent: Entity,
+    /// The first child, where the circular list wraps around
+    first: Entity,
     current: Entity,
     remaining: usize,
 }
 
-pub struct DepthFirstIterator<'a, T: Component> {
+impl StackFrame {
+    fn new(parent: Entity, first: Entity, remaining: usize) -> Self {
+        Self {
+            parent,
+            first,
+            current: first,
+            remaining,
+        }
+    }
+
+    /// Moves to the next sibling. Stops when wrapping around to the first child, even if
+    /// `num_children` claims there are more children, in which case true is returned.
+    fn advance(&mut self, next: Entity) -> bool {
+        self.current = next;
+        self.remaining -= 1;
+
+        if next == self.first {
+            let short = self.remaining > 0;
+            self.remaining = 0;
+            short
+        } else {
+            false
+        }
+    }
+}
+
+pub struct DepthFirstIterator<'a, W, T: Component> {
+    world: &'a W,
     children: QueryBorrow<'a, &'a Child<T>>,
     parents: QueryBorrow<'a, &'a Parent<T>>,
     marker: PhantomData<T>,
     /// Since StackFrame is so small, use smallvec optimizations
     stack: SmallVec<[StackFrame; STACK_SIZE]>,
+    /// The deepest level to descend to, where the immediate children of the root are at level 1
+    max_depth: usize,
+    /// The number of descendants yet to be yielded, from the cached subtree size. Only an upper
+    /// bound, since the depth may be limited or the cache stale after a plain despawn.
+    remaining: usize,
+    /// Whether `remaining` is exact, which holds as long as the depth is unlimited and no broken
+    /// link has been met. Since entities need to be detached before being removed, the cached
+    /// subtree size is trusted otherwise.
+    exact: bool,
 }
 
-impl<'a, T: Component> DepthFirstIterator<'a, T> {
-    pub(crate) fn new<W: GenericWorld>(world: &'a W, root: Entity) -> Self {
-        let children = world.try_query().unwrap();
+impl<'a, W: GenericWorld, T: Component> DepthFirstIterator<'a, W, T> {
+    pub(crate) fn new(world: &'a W, root: Entity) -> Self {
+        Self::new_limited(world, root, usize::MAX)
+    }
+
+    pub(crate) fn new_limited(world: &'a W, root: Entity, max_depth: usize) -> Self {
+        let mut children = world.try_query::<&Child<T>>().unwrap();
         let mut parents = world.try_query::<&Parent<T>>().unwrap();
 
-        let stack = parents
+        let (stack, remaining, linked) = parents
             .view()
             .get(root)
-            .and_then(|parent| {
-                let first_child = parent.first_child(world).ok()?;
-                Some(smallvec![StackFrame {
-                    current: first_child,
-                    remaining: parent.num_children,
-                }])
+            .filter(|_| max_depth > 0)
+            .map(|parent| {
+                (
+                    smallvec![StackFrame::new(
+                        root,
+                        parent.first_child(),
+                        parent.num_children
+                    )],
+                    parent.subtree_size,
+                    parent.view_first_child(&children.view()).is_ok(),
+                )
             })
-            .unwrap_or_default();
+            .unwrap_or((SmallVec::new(), 0, true));
 
         Self {
+            world,
             children,
             parents,
             stack,
+            max_depth,
+            remaining,
+            exact: linked && max_depth == usize::MAX,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, W, T: Component> std::fmt::Debug for DepthFirstIterator<'a, W, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DepthFirstIterator")
+            .field("marker", &std::any::type_name::<T>())
+            .field("stack", &self.stack)
+            .field("max_depth", &self.max_depth)
+            .field("remaining", &self.remaining)
+            .field("exact", &self.exact)
+            .finish()
+    }
+}
+
+impl<'a, W: GenericWorld, T: Component> Clone for DepthFirstIterator<'a, W, T> {
+    fn clone(&self) -> Self {
+        Self {
+            world: self.world,
+            children: self.world.try_query().unwrap(),
+            parents: self.world.try_query().unwrap(),
+            stack: self.stack.clone(),
+            max_depth: self.max_depth,
+            remaining: self.remaining,
+            exact: self.exact,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Depth first traversal which does not borrow the world, but instead takes it on each
+/// [step](DepthFirstCursor::step). This allows the traversal to be paused and resumed later, e.g.
+/// by storing the cursor in a component between frames.
+pub struct DepthFirstCursor<T> {
+    /// The root whose children are yet to be pushed on the first step
+    root: Option<Entity>,
+    /// Since StackFrame is so small, use smallvec optimizations
+    stack: SmallVec<[StackFrame; STACK_SIZE]>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component> DepthFirstCursor<T> {
+    /// Construct a cursor traversing the descendants of `root`, not including `root` itself.
+    pub fn new(root: Entity) -> Self {
+        Self {
+            root: Some(root),
+            stack: SmallVec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Advances the traversal and returns the next descendant, or `None` if the traversal is
+    /// complete.
+    pub fn step(&mut self, world: &impl GenericWorld) -> Option<Entity> {
+        if let Some(root) = self.root.take() {
+            if let Ok(parent) = world.try_get::<Parent<T>>(root) {
+                self.stack.push(StackFrame::new(
+                    root,
+                    parent.first_child(),
+                    parent.num_children,
+                ))
+            }
+        }
+
+        loop {
+            // The the topmost stackframe
+            let top = self.stack.last_mut()?;
+
+            // There are more children in current stackframe
+            if top.remaining > 0 {
+                let current = top.current;
+
+                // Go to the next child in the linked list of children
+                let next = match world.try_get::<Child<T>>(current) {
+                    Ok(data) if data.parent == top.parent => data.next,
+                    _ => {
+                        // The child was despawned or unlinked, which ends this chain of siblings
+                        self.stack.pop();
+                        continue;
+                    }
+                };
+                top.advance(next);
+
+                // If current is a parent, push a new stack frame with the first child
+                if let Ok(parent) = world.try_get::<Parent<T>>(current) {
+                    self.stack.push(StackFrame::new(
+                        current,
+                        parent.first_child(),
+                        parent.num_children,
+                    ))
+                }
+
+                return Some(current);
+            } else {
+                // End of linked list of children, pop stack frame
+                self.stack.pop();
+            }
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for DepthFirstCursor<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DepthFirstCursor")
+            .field("marker", &std::any::type_name::<T>())
+            .field("root", &self.root)
+            .field("stack", &self.stack)
+            .finish()
+    }
+}
+
+impl<T> Clone for DepthFirstCursor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root,
+            stack: self.stack.clone(),
             marker: PhantomData,
         }
     }
@@ -156,11 +506,11 @@ impl<'a, F: Fn(&W, Entity) -> bool + Component, W: GenericWorld, T: Component>
             .get(root)
             .and_then(|parent| {
                 if (accept)(world, root) {
-                    let first_child = parent.first_child(world).ok()?;
-                    Some(smallvec![StackFrame {
-                        current: first_child,
-                        remaining: parent.num_children,
-                    }])
+                    Some(smallvec![StackFrame::new(
+                        root,
+                        parent.first_child(),
+                        parent.num_children
+                    )])
                 } else {
                     None
                 }
@@ -192,11 +542,17 @@ impl<'a, F: Fn(&W, Entity) -> bool + Component, W: GenericWorld, T: Component> I
                 let current = top.current;
 
                 let children = self.children.view();
-                let data = children.get(top.current).unwrap();
+                let data = match children.get(current) {
+                    Some(data) if data.parent == top.parent => data,
+                    _ => {
+                        // The child was despawned or unlinked, which ends this chain of siblings
+                        self.stack.pop();
+                        continue;
+                    }
+                };
 
                 // Go to the next child in the linked list of children
-                top.current = data.next;
-                top.remaining -= 1;
+                top.advance(data.next);
 
                 if !(self.accept)(self.world, current) {
                     continue;
@@ -205,10 +561,8 @@ impl<'a, F: Fn(&W, Entity) -> bool + Component, W: GenericWorld, T: Component> I
                 // If current is a parent, push a new stack frame with the first child
                 if let Some(parent) = self.parents.view().get(current) {
                     if let Ok(first_child) = parent.view_first_child(&children) {
-                        self.stack.push(StackFrame {
-                            current: first_child,
-                            remaining: parent.num_children,
-                        })
+                        self.stack
+                            .push(StackFrame::new(current, first_child, parent.num_children))
                     }
                 }
 
@@ -221,73 +575,412 @@ impl<'a, F: Fn(&W, Entity) -> bool + Component, W: GenericWorld, T: Component> I
     }
 }
 
-impl<'a, T: Component> Iterator for DepthFirstIterator<'a, T> {
+/// Outcome of the visit function of [DepthFirstPrunedVisitor](crate::DepthFirstPrunedVisitor)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    /// Yield the node and descend into its children
+    Yield,
+    /// Hide the node but descend into its children
+    Skip,
+    /// Hide the node and its whole subtree
+    Prune,
+}
+
+pub struct DepthFirstPrunedVisitor<'a, W, T: Component, F> {
+    world: &'a W,
+    children: QueryBorrow<'a, &'a Child<T>>,
+    parents: QueryBorrow<'a, &'a Parent<T>>,
+    marker: PhantomData<T>,
+    /// Since StackFrame is so small, use smallvec optimizations
+    stack: SmallVec<[StackFrame; STACK_SIZE]>,
+    visit: F,
+}
+
+impl<'a, F: Fn(&W, Entity) -> Visit + Component, W: GenericWorld, T: Component>
+    DepthFirstPrunedVisitor<'a, W, T, F>
+{
+    pub(crate) fn new(world: &'a W, root: Entity, visit: F) -> Self {
+        let children = world.try_query().unwrap();
+        let mut parents = world.try_query::<&Parent<T>>().unwrap();
+
+        let stack = parents
+            .view()
+            .get(root)
+            .and_then(|parent| {
+                if (visit)(world, root) != Visit::Prune {
+                    Some(smallvec![StackFrame::new(
+                        root,
+                        parent.first_child(),
+                        parent.num_children
+                    )])
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            world,
+            visit,
+            children,
+            parents,
+            stack,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, F: Fn(&W, Entity) -> Visit + Component, W: GenericWorld, T: Component> Iterator
+    for DepthFirstPrunedVisitor<'a, W, T, F>
+{
     type Item = Entity;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // The the topmost stackframe
-        let top = self.stack.last_mut()?;
-
-        // There are more children in current stackframe
-        if top.remaining > 0 {
-            let current = top.current;
-
-            let children = self.children.view();
-            let data = children.get(top.current).unwrap();
-
-            // Go to the next child in the linked list of children
-            top.current = data.next;
-            top.remaining -= 1;
-
-            // If current is a parent, push a new stack frame with the first child
-            if let Some(parent) = self.parents.view().get(current) {
-                if let Ok(first_child) = parent.view_first_child(&children) {
-                    self.stack.push(StackFrame {
-                        current: first_child,
-                        remaining: parent.num_children,
-                    })
+        loop {
+            // The the topmost stackframe
+            let top = self.stack.last_mut()?;
+            // There are more children in current stackframe
+            if top.remaining > 0 {
+                let current = top.current;
+
+                let children = self.children.view();
+                let data = match children.get(current) {
+                    Some(data) if data.parent == top.parent => data,
+                    _ => {
+                        // The child was despawned or unlinked, which ends this chain of siblings
+                        self.stack.pop();
+                        continue;
+                    }
+                };
+
+                // Go to the next child in the linked list of children
+                top.advance(data.next);
+
+                let visit = (self.visit)(self.world, current);
+                if visit == Visit::Prune {
+                    continue;
+                }
+
+                // If current is a parent, push a new stack frame with the first child
+                if let Some(parent) = self.parents.view().get(current) {
+                    if let Ok(first_child) = parent.view_first_child(&children) {
+                        self.stack
+                            .push(StackFrame::new(current, first_child, parent.num_children))
+                    }
                 }
+
+                if visit == Visit::Yield {
+                    return Some(current);
+                }
+            } else {
+                // End of linked list of children, pop stack frame
+                self.stack.pop();
+            }
+        }
+    }
+}
+
+impl<'a, W: GenericWorld, T: Component> DepthFirstIterator<'a, W, T> {
+    /// Advances the traversal, returning the next descendant along with its depth below the
+    /// root. The immediate children of the root are at depth 1.
+    pub(crate) fn next_with_depth(&mut self) -> Option<(Entity, usize)> {
+        loop {
+            // The the topmost stackframe
+            let top = self.stack.last_mut()?;
+
+            // There are more children in current stackframe
+            if top.remaining > 0 {
+                let current = top.current;
+
+                let children = self.children.view();
+                let data = match children.get(current) {
+                    Some(data) if data.parent == top.parent => data,
+                    _ => {
+                        // The child was despawned or unlinked, which ends this chain of siblings
+                        self.stack.pop();
+                        self.exact = false;
+                        continue;
+                    }
+                };
+
+                // Go to the next child in the linked list of children
+                if top.advance(data.next) {
+                    // The list is shorter than `num_children`, so the cache is stale
+                    self.exact = false;
+                }
+
+                let depth = self.stack.len();
+
+                // If current is a parent, push a new stack frame with the first child unless the
+                // maximum depth is reached
+                if depth < self.max_depth {
+                    if let Some(parent) = self.parents.view().get(current) {
+                        if let Ok(first_child) = parent.view_first_child(&children) {
+                            self.stack.push(StackFrame::new(
+                                current,
+                                first_child,
+                                parent.num_children,
+                            ))
+                        }
+                    }
+                }
+
+                self.remaining = self.remaining.saturating_sub(1);
+                return Some((current, depth));
+            } else {
+                // End of linked list of children, pop stack frame
+                self.stack.pop();
             }
+        }
+    }
+}
 
-            Some(current)
+impl<'a, W: GenericWorld, T: Component> Iterator for DepthFirstIterator<'a, W, T> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_depth().map(|(entity, _)| entity)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.stack.is_empty() {
+            (0, Some(0))
+        } else if self.exact {
+            (self.remaining, Some(self.remaining))
         } else {
-            // End of linked list of children, pop stack frame
-            self.stack.pop();
-            self.next()
+            // The depth is limited or a broken link ended the traversal of a subtree early
+            (0, Some(self.remaining))
+        }
+    }
+
+    fn count(self) -> usize
+    where
+        Self: Sized,
+    {
+        if self.stack.is_empty() {
+            0
+        } else if self.exact {
+            self.remaining
+        } else {
+            self.fold(0, |count, _| count + 1)
         }
     }
 }
 
-pub struct BreadthFirstIterator<'a, W, T> {
+pub struct BreadthFirstIterator<'a, W, T: Component> {
     world: &'a W,
+    children: QueryBorrow<'a, &'a Child<T>>,
+    parents: QueryBorrow<'a, &'a Parent<T>>,
     marker: PhantomData<T>,
-    queue: VecDeque<Entity>,
+    queue: SmallVecDequeue<[Entity; QUEUE_SIZE]>,
+    /// The number of nodes at the front of the queue whose children have already been queued
+    expanded: usize,
 }
 
-impl<'a, W: GenericWorld + Hierarchy, T: 'static + Send + Sync> BreadthFirstIterator<'a, W, T> {
+impl<'a, W: GenericWorld + Hierarchy, T: Component> BreadthFirstIterator<'a, W, T> {
     pub(crate) fn new(world: &'a W, root: Entity) -> Self {
+        let mut iter = Self::empty(world);
         // Add immediate children of root to queue
-        let queue = world.children::<T>(root).collect();
+        iter.push_children(root);
+        iter
+    }
 
+    /// Construct an iterator with an empty queue
+    fn empty(world: &'a W) -> Self {
         Self {
             world,
-            queue,
+            children: world.try_query().unwrap(),
+            parents: world.try_query().unwrap(),
+            queue: SmallVecDequeue::new(),
+            expanded: 0,
             marker: PhantomData,
         }
     }
 }
 
-impl<'a, W: GenericWorld + Hierarchy, T: 'static + Send + Sync> Iterator
-    for BreadthFirstIterator<'a, W, T>
-{
+impl<'a, W, T: Component> BreadthFirstIterator<'a, W, T> {
+    /// Add any potential children of `entity` to the back of the queue
+    fn push_children(&mut self, entity: Entity) {
+        let children = self.children.view();
+        if let Some(parent) = self.parents.view().get(entity) {
+            if let Ok(first) = parent.view_first_child(&children) {
+                let mut current = first;
+                for _ in 0..parent.num_children {
+                    match children.get(current) {
+                        Some(data) if data.parent == entity => {
+                            self.queue.push(current);
+                            current = data.next;
+                        }
+                        // The child was despawned or unlinked, which ends the chain of siblings
+                        _ => break,
+                    }
+
+                    // Stop when wrapping around, even if `num_children` claims there are more
+                    // children
+                    if current == first {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, W, T: Component> std::fmt::Debug for BreadthFirstIterator<'a, W, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BreadthFirstIterator")
+            .field("marker", &std::any::type_name::<T>())
+            .field("queue", &self.queue)
+            .field("expanded", &self.expanded)
+            .finish()
+    }
+}
+
+impl<'a, W: GenericWorld, T: Component> Clone for BreadthFirstIterator<'a, W, T> {
+    fn clone(&self) -> Self {
+        Self {
+            world: self.world,
+            children: self.world.try_query().unwrap(),
+            parents: self.world.try_query().unwrap(),
+            queue: self.queue.clone(),
+            expanded: self.expanded,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, W: GenericWorld + Hierarchy, T: Component> Iterator for BreadthFirstIterator<'a, W, T> {
     type Item = Entity;
 
     fn next(&mut self) -> Option<Self::Item> {
         let front = self.queue.pop_front()?;
 
-        // Add any potention children of front to the back of queue
-        self.queue.extend(self.world.children::<T>(front));
+        if self.expanded > 0 {
+            // The children of front were already queued by `next_back`
+            self.expanded -= 1;
+        } else {
+            self.push_children(front);
+        }
 
         Some(front)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.expanded == self.queue.len() {
+            // Everything remaining has been discovered
+            (self.queue.len(), Some(self.queue.len()))
+        } else {
+            // At least everything queued remains
+            (self.queue.len(), None)
+        }
+    }
+}
+
+/// Iterating from the back yields the last nodes of the breadth first order first, i.e. the
+/// deepest nodes. Combining [next](Iterator::next) and [next_back](Self::next_back) consumes the
+/// order from both ends until they meet, without yielding any node twice.
+impl<'a, W: GenericWorld + Hierarchy, T: Component> DoubleEndedIterator
+    for BreadthFirstIterator<'a, W, T>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // The back is only known once the remaining traversal has been discovered
+        while let Some(entity) = self.queue.get(self.expanded) {
+            self.expanded += 1;
+            self.push_children(entity);
+        }
+
+        let back = self.queue.pop_back()?;
+        self.expanded = self.queue.len();
+
+        Some(back)
+    }
+}
+
+/// Breadth first iterator which also yields the level of each node. The immediate children of the
+/// root are at level 1.
+pub struct BreadthFirstDepthIterator<'a, W, T: Component> {
+    iter: BreadthFirstIterator<'a, W, T>,
+    /// Level of the nodes currently at the front of the queue
+    depth: usize,
+    /// Number of nodes left to yield at `depth`
+    level_remaining: usize,
+}
+
+impl<'a, W: GenericWorld + Hierarchy, T: Component> BreadthFirstDepthIterator<'a, W, T> {
+    pub(crate) fn new(world: &'a W, root: Entity) -> Self {
+        let iter = BreadthFirstIterator::new(world, root);
+        let level_remaining = iter.queue.len();
+
+        Self {
+            iter,
+            depth: 1,
+            level_remaining,
+        }
+    }
+}
+
+impl<'a, W: GenericWorld + Hierarchy, T: Component> Iterator
+    for BreadthFirstDepthIterator<'a, W, T>
+{
+    type Item = (Entity, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.level_remaining == 0 {
+            // Everything left in the queue was discovered from the previous level
+            self.depth += 1;
+            self.level_remaining = self.iter.queue.len();
+        }
+
+        let front = self.iter.next()?;
+        self.level_remaining -= 1;
+
+        Some((front, self.depth))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Breadth first iterator with an acceptance function. A node which is not accepted is skipped
+/// along with its whole subtree.
+pub struct BreadthFirstVisitor<'a, W, T: Component, F> {
+    iter: BreadthFirstIterator<'a, W, T>,
+    accept: F,
+}
+
+impl<'a, W: GenericWorld + Hierarchy, T: Component, F: Fn(&W, Entity) -> bool + Component>
+    BreadthFirstVisitor<'a, W, T, F>
+{
+    pub(crate) fn new(world: &'a W, root: Entity, accept: F) -> Self {
+        let mut iter = BreadthFirstIterator::empty(world);
+
+        // Add immediate children of root to queue
+        if (accept)(world, root) {
+            iter.push_children(root);
+        }
+
+        Self { iter, accept }
+    }
+}
+
+impl<'a, W: GenericWorld + Hierarchy, T: Component, F: Fn(&W, Entity) -> bool + Component> Iterator
+    for BreadthFirstVisitor<'a, W, T, F>
+{
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let front = self.iter.queue.pop_front()?;
+
+            // Skip the node and prune its subtree
+            if !(self.accept)(self.iter.world, front) {
+                continue;
+            }
+
+            self.iter.push_children(front);
+
+            return Some(front);
+        }
+    }
 }
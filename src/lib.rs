@@ -13,8 +13,8 @@
 //! - [X] Traverse hierarchy breadth first
 //! - [X] Traverse ancestors
 //! - [X] Detach child from hierarchy
-//! - [ ] Reverse iteration
-//! - [ ] Sorting
+//! - [X] Reverse iteration
+//! - [X] Sorting
 //! - [ ] (Optional) associated data to relation
 //!
 //! ## Getting Started
@@ -93,6 +93,10 @@
 //! // Detach `child` and `grandchild`
 //! world.detach::<Tree>(child).unwrap();
 //!
+//! // `child` is no longer a `Child` in the tree, but keeps its own children
+//! assert!(world.get::<&Child<Tree>>(child).is_err());
+//! assert!(world.get::<&Parent<Tree>>(child).is_ok());
+//!
 //! let child2 = world.attach_new::<Tree, _>(root, ("Child 2",)).unwrap();
 //!
 //! // Reattach as a child of `child2`
@@ -125,14 +129,25 @@
 
 mod builder;
 mod builder_clone;
+mod commands;
 mod components;
+mod defect;
+pub mod error;
 mod hierarchy;
 mod iter;
+mod observer;
+mod smallvec_dequeue;
+mod tree;
 
 pub use builder::*;
 pub use builder_clone::*;
+pub use commands::*;
 pub use components::*;
+pub use defect::*;
+pub use error::HierarchyError;
 pub use hierarchy::*;
 pub use iter::*;
+pub use observer::*;
+pub use tree::*;
 
 pub use hecs_schedule::Error;
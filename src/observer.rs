@@ -0,0 +1,19 @@
+use hecs::Entity;
+
+/// Receives structural changes of a tree, which avoids polling the hierarchy for differences.
+/// Pass an observer to
+/// [HierarchyMut::attach_with_observer](crate::HierarchyMut::attach_with_observer) or
+/// [HierarchyMut::detach_with_observer](crate::HierarchyMut::detach_with_observer).
+///
+/// All methods default to doing nothing, so only the events of interest need to be implemented.
+pub trait HierarchyObserver {
+    /// Called after `child` was attached to `parent`.
+    fn on_attach(&mut self, child: Entity, parent: Entity) {
+        let _ = (child, parent);
+    }
+
+    /// Called after `child` was detached from `parent`.
+    fn on_detach(&mut self, child: Entity, parent: Entity) {
+        let _ = (child, parent);
+    }
+}
@@ -3,115 +3,214 @@ use std::iter::FromIterator;
 use smallvec::{Array, SmallVec};
 
 /// Dequeue version of small vec
+///
+/// The buffer is used as a ring buffer, and its length is always a power of two, so that indices
+/// can be wrapped using a mask.
 pub struct SmallVecDequeue<T: Array> {
     /// Offset to the first element
     front: usize,
-    /// Offset to where data should be written
-    back: usize,
+    /// Number of elements in the queue
+    len: usize,
     buf: SmallVec<T>,
 }
 
-impl<T: Array> SmallVecDequeue<T> {
+impl<U: Copy, T: Array<Item = U>> SmallVecDequeue<T> {
+    pub fn new() -> Self {
+        Self {
+            front: 0,
+            len: 0,
+            buf: SmallVec::new(),
+        }
+    }
+
     pub fn capacity(&self) -> usize {
-        self.buf.capacity()
+        self.buf.len()
     }
 
     pub fn len(&self) -> usize {
-        count(self.front, self.back, self.capacity())
+        self.len
     }
 
     pub fn is_empty(&self) -> bool {
-        self.front == self.back
+        self.len == 0
     }
 
-    fn grow(&mut self) {
-        let old = self.capacity();
-        self.buf.reserve(1);
-        let new_cap = self.buf.capacity();
+    /// Returns the buffer index of the `i`th element
+    #[inline]
+    fn wrap(&self, i: usize) -> usize {
+        // capacity is always a power of 2
+        (self.front + i) & (self.capacity() - 1)
+    }
 
-        // Buffer is contiguous; do nothing
-        if self.front <= self.back {
-        }
-        // Head is behind tail
-        else if self.back < old - self.front {
-            unsafe {
-                std::ptr::copy_nonoverlapping(
-                    self.buf.as_ptr().add(old),
-                    self.buf.as_mut_ptr(),
-                    self.back,
-                )
-            }
+    /// Returns the `i`th element from the front
+    pub fn get(&self, i: usize) -> Option<U> {
+        if i < self.len {
+            Some(self.buf[self.wrap(i)])
         } else {
-            let new_front = new_cap - (old - self.front);
-            unsafe {
-                std::ptr::copy_nonoverlapping(
-                    self.buf.as_ptr().add(new_front),
-                    self.buf.as_mut_ptr().add(self.front),
-                    old - self.front,
-                )
-            };
-
-            self.front = new_front;
-            debug_assert!(self.back < self.front);
+            None
         }
-        debug_assert!(self.back < self.capacity());
-        debug_assert!(self.front < self.capacity());
+    }
+
+    /// Iterate the elements from front to back
+    fn iter(&self) -> impl Iterator<Item = U> + '_ {
+        (0..self.len).map(move |i| self.buf[self.wrap(i)])
+    }
+
+    /// Grow the buffer, moving the elements to the start of the new buffer. Unused slots are
+    /// filled with `fill`.
+    fn grow(&mut self, fill: U) {
+        let new_cap = (self.capacity() * 2)
+            .max(T::size())
+            .max(1)
+            .next_power_of_two();
+
+        let mut buf = SmallVec::with_capacity(new_cap);
+        buf.extend(self.iter());
+        buf.resize(new_cap, fill);
+
+        self.buf = buf;
+        self.front = 0;
+
         debug_assert!(self.capacity().count_ones() == 1);
     }
 
-    pub fn push(&mut self, val: T::Item) {
-        dbg!(self.len());
-        if self.len() == self.capacity() {
-            dbg!("Growing");
-            self.grow();
+    pub fn push(&mut self, val: U) {
+        if self.len == self.capacity() {
+            self.grow(val);
         }
-        let back = self.back;
-        self.back = (back + 1) & self.capacity();
 
-        eprintln!("{}, {}", back, self.buf.len());
-        if back < self.buf.len() {
-            self.buf[back] = val;
-        } else {
-            self.push(val)
-        }
+        let back = self.wrap(self.len);
+        self.buf[back] = val;
+        self.len += 1;
     }
-}
 
-impl<U: Copy, T: Array<Item = U>> SmallVecDequeue<T> {
     /// Removes the first element and returns it
-    pub fn pop_front(&mut self) -> Option<T::Item> {
+    pub fn pop_front(&mut self) -> Option<U> {
         if self.is_empty() {
             None
         } else {
-            let front = self.front;
-            self.front = (front + 1) % self.capacity();
-            Some(self.buf[front])
+            let val = self.buf[self.front];
+            self.front = self.wrap(1);
+            self.len -= 1;
+            Some(val)
         }
     }
-}
 
-/// Calculate the number of elements left to be read in the buffer
-#[inline]
-fn count(front: usize, back: usize, size: usize) -> usize {
-    // size is always a power of 2
-    (back.wrapping_sub(front)) & (size - 1)
+    /// Removes the last element and returns it
+    pub fn pop_back(&mut self) -> Option<U> {
+        if self.is_empty() {
+            None
+        } else {
+            self.len -= 1;
+            Some(self.buf[self.wrap(self.len)])
+        }
+    }
 }
 
-impl<T: Array> FromIterator<T::Item> for SmallVecDequeue<T> {
-    fn from_iter<U: IntoIterator<Item = T::Item>>(iter: U) -> Self {
-        let buf = SmallVec::from_iter(iter);
+impl<U: Copy, T: Array<Item = U>> Clone for SmallVecDequeue<T> {
+    fn clone(&self) -> Self {
         Self {
-            front: 0,
-            back: buf.len(),
-            buf,
+            front: self.front,
+            len: self.len,
+            buf: self.buf.clone(),
         }
     }
 }
 
-impl<T: Array> Extend<T::Item> for SmallVecDequeue<T> {
-    fn extend<U: IntoIterator<Item = T::Item>>(&mut self, iter: U) {
-        let iter = iter.into_iter();
+impl<U: Copy + std::fmt::Debug, T: Array<Item = U>> std::fmt::Debug for SmallVecDequeue<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<U: Copy, T: Array<Item = U>> Default for SmallVecDequeue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<U: Copy, T: Array<Item = U>> FromIterator<U> for SmallVecDequeue<T> {
+    fn from_iter<I: IntoIterator<Item = U>>(iter: I) -> Self {
+        let mut queue = Self::new();
+        queue.extend(iter);
+        queue
+    }
+}
+
+impl<U: Copy, T: Array<Item = U>> Extend<U> for SmallVecDequeue<T> {
+    fn extend<I: IntoIterator<Item = U>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|val| self.push(val))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::SmallVecDequeue;
+
+    #[test]
+    fn grow() {
+        let mut queue = SmallVecDequeue::<[usize; 4]>::new();
+        let mut expected = VecDeque::new();
+
+        // Interleave pushes and pops so that the ring buffer wraps before growing
+        for i in 0..256 {
+            queue.push(i);
+            expected.push_back(i);
+
+            if i % 3 == 0 {
+                assert_eq!(queue.pop_front(), expected.pop_front());
+            }
+
+            assert_eq!(queue.len(), expected.len());
+            assert!(queue.capacity() >= queue.len());
+            assert_eq!(queue.capacity().count_ones(), 1);
+        }
+
+        while let Some(val) = expected.pop_front() {
+            assert_eq!(queue.pop_front(), Some(val));
+        }
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop_front(), None);
+    }
+
+    #[test]
+    fn pop_back() {
+        let mut queue = SmallVecDequeue::<[usize; 4]>::new();
+        let mut expected = VecDeque::new();
+
+        for i in 0..64 {
+            queue.push(i);
+            expected.push_back(i);
+
+            match i % 4 {
+                0 => assert_eq!(queue.pop_front(), expected.pop_front()),
+                1 => assert_eq!(queue.pop_back(), expected.pop_back()),
+                _ => {}
+            }
+
+            assert_eq!(queue.get(0), expected.front().copied());
+            assert_eq!(queue.get(queue.len()), None);
+        }
+
+        while let Some(val) = expected.pop_back() {
+            assert_eq!(queue.pop_back(), Some(val));
+        }
+
+        assert_eq!(queue.pop_back(), None);
+    }
+
+    #[test]
+    fn from_iter() {
+        let mut queue = (0..37).collect::<SmallVecDequeue<[u32; 8]>>();
+        queue.extend(37..40);
 
-        iter.for_each(|val| self.push(val))
+        assert_eq!(queue.len(), 40);
+        assert_eq!(
+            std::iter::from_fn(|| queue.pop_front()).collect::<Vec<_>>(),
+            (0..40).collect::<Vec<_>>()
+        );
     }
 }
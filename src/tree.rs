@@ -0,0 +1,99 @@
+use std::marker::PhantomData;
+
+use hecs::{Component, DynamicBundle, Entity, World};
+
+use crate::{
+    error::Result, AncestorIter, BreadthFirstIterator, ChildrenIter, DepthFirstIterator, Hierarchy,
+    HierarchyMut,
+};
+
+/// Borrows the world with the hierarchy marker `T` fixed, which removes the need to name the
+/// marker on every call.
+///
+/// # Example
+/// ```rust
+/// use hecs_hierarchy::*;
+/// use hecs::*;
+///
+/// struct Marker;
+/// let mut world = World::default();
+/// let mut tree = world.tree::<Marker>();
+///
+/// let root = tree.world().spawn(("root",));
+/// let child = tree.attach_new(root, ("child",)).unwrap();
+///
+/// assert_eq!(tree.parent(child).unwrap(), root);
+/// assert_eq!(tree.children(root).collect::<Vec<_>>(), [child]);
+/// ```
+pub struct Tree<'a, T> {
+    world: &'a mut World,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: Component> Tree<'a, T> {
+    pub(crate) fn new(world: &'a mut World) -> Self {
+        Self {
+            world,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the borrowed world.
+    pub fn world(&mut self) -> &mut World {
+        self.world
+    }
+
+    /// See [Hierarchy::parent].
+    pub fn parent(&self, child: Entity) -> Result<Entity> {
+        self.world.parent::<T>(child)
+    }
+
+    /// See [Hierarchy::root].
+    pub fn root(&self, child: Entity) -> Result<Entity> {
+        self.world.root::<T>(child)
+    }
+
+    /// See [Hierarchy::children].
+    pub fn children(&self, parent: Entity) -> ChildrenIter<'_, T> {
+        self.world.children::<T>(parent)
+    }
+
+    /// See [Hierarchy::ancestors].
+    pub fn ancestors(&self, child: Entity) -> AncestorIter<'_, T> {
+        self.world.ancestors::<T>(child)
+    }
+
+    /// See [Hierarchy::descendants_depth_first].
+    pub fn descendants_depth_first(&self, root: Entity) -> DepthFirstIterator<'_, World, T> {
+        self.world.descendants_depth_first::<T>(root)
+    }
+
+    /// See [Hierarchy::descendants_breadth_first].
+    pub fn descendants_breadth_first(&self, root: Entity) -> BreadthFirstIterator<'_, World, T> {
+        self.world.descendants_breadth_first::<T>(root)
+    }
+
+    /// See [HierarchyMut::attach].
+    pub fn attach(&mut self, child: Entity, parent: Entity) -> Result<Entity> {
+        self.world.attach::<T>(child, parent)
+    }
+
+    /// See [HierarchyMut::attach_new].
+    pub fn attach_new<C: DynamicBundle>(
+        &mut self,
+        parent: Entity,
+        components: C,
+    ) -> Result<Entity> {
+        self.world.attach_new::<T, C>(parent, components)
+    }
+
+    /// See [HierarchyMut::detach].
+    pub fn detach(&mut self, child: Entity) -> Result<Option<Entity>> {
+        self.world.detach::<T>(child)
+    }
+
+    /// See [HierarchyMut::despawn_all].
+    pub fn despawn_all(&mut self, parent: Entity) -> usize {
+        self.world.despawn_all::<T>(parent)
+    }
+}
@@ -1,8 +1,16 @@
-use std::collections::HashSet;
+#![allow(
+    clippy::bool_comparison,
+    clippy::iter_cloned_collect,
+    clippy::manual_inspect
+)]
 
-use hecs::{Entity, World};
+use std::collections::{HashMap, HashSet};
+
+use hecs::{Entity, EntityBuilderClone, World};
 use hecs_hierarchy::{
-    Child, Hierarchy, HierarchyMut, HierarchyQuery, TreeBuilder, TreeBuilderClone,
+    Child, DepthFirstCursor, Hierarchy, HierarchyCommandBuffer, HierarchyDefect, HierarchyError,
+    HierarchyMut, HierarchyObserver, HierarchyQuery, OrderIndex, Parent, TreeBuilder,
+    TreeBuilderClone, Visit,
 };
 use hecs_schedule::{CommandBuffer, GenericWorld, SubWorldRef};
 
@@ -66,7 +74,7 @@ fn reattach2() {
         println!("{:?}", *world.get::<&&str>(e).unwrap());
     }
 
-    world.detach::<Tree>(child2).unwrap();
+    assert_eq!(world.detach::<Tree>(child2).unwrap(), Some(root));
     world.attach::<Tree>(child2, child1).unwrap();
     for e in world.descendants_depth_first::<Tree>(root) {
         println!("{:?}", *world.get::<&&str>(e).unwrap());
@@ -116,7 +124,7 @@ fn detach() {
     let child5 = world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
 
     // Remove child2, and by extension child3
-    world.detach::<Tree>(child2).unwrap();
+    assert_eq!(world.detach::<Tree>(child2).unwrap(), Some(root));
 
     let order = [child1, child4, child5];
 
@@ -285,6 +293,40 @@ fn bfs() {
     );
 }
 
+#[test]
+fn bfs_wide() {
+    let width = 100;
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+
+    let level1 = (0..width)
+        .map(|_| world.attach_new::<Tree, _>(root, ("Child",)).unwrap())
+        .collect::<Vec<_>>();
+
+    let level2 = level1
+        .iter()
+        .flat_map(|&parent| {
+            (0..width)
+                .map(|_| {
+                    world
+                        .attach_new::<Tree, _>(parent, ("Grandchild",))
+                        .unwrap()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let order = level1.iter().chain(&level2).copied().collect::<Vec<_>>();
+
+    assert_eq!(
+        world
+            .descendants_breadth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        order
+    );
+}
+
 #[test]
 fn empty() {
     let mut world = World::default();
@@ -460,3 +502,3217 @@ fn reserve() {
         assert_eq!(*world.get::<&&str>(a).unwrap(), b)
     }
 }
+
+#[test]
+fn reserve_builder() {
+    let mut world = World::default();
+    let mut builder = TreeBuilder::<Tree>::from(("root",));
+    builder.attach(("child 1",));
+    builder.attach({
+        let mut builder = TreeBuilder::new();
+        builder.add("child 2");
+        builder
+    });
+
+    let root = builder.reserve(&world);
+
+    // Reserving again yields the same entity
+    assert_eq!(builder.reserve(&world), root);
+
+    assert_eq!(builder.spawn(&mut world), root);
+
+    assert_eq!(*world.get::<&&'static str>(root).unwrap(), "root");
+
+    for (a, b) in world
+        .descendants_depth_first::<Tree>(root)
+        .zip(["child 1", "child 2"])
+    {
+        assert_eq!(*world.get::<&&str>(a).unwrap(), b)
+    }
+}
+
+#[test]
+fn move_child() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    world.move_child::<Tree>(child4, 1).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child4, child2, child3, child5]
+    );
+
+    // Same index is a no-op
+    world.move_child::<Tree>(child4, 1).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child4, child2, child3, child5]
+    );
+
+    // Out of bounds appends
+    world.move_child::<Tree>(child1, 100).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child4, child2, child3, child5, child1]
+    );
+}
+
+#[test]
+fn swap_siblings() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    // Adjacent
+    world.swap_siblings::<Tree>(child2, child3).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child3, child2, child4, child5]
+    );
+
+    // Adjacent, reversed argument order
+    world.swap_siblings::<Tree>(child5, child4).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child3, child2, child5, child4]
+    );
+
+    // Non adjacent, including first and last
+    world.swap_siblings::<Tree>(child1, child4).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child4, child3, child2, child5, child1]
+    );
+
+    // Different parents
+    let other = world.attach_new::<Tree, _>(child1, ("Other",)).unwrap();
+    assert!(world.swap_siblings::<Tree>(child2, other).is_err());
+}
+
+#[test]
+fn attach_front() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.spawn(("Child1",));
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+
+    world.attach_front::<Tree>(child1, root).unwrap();
+
+    assert_eq!(world.children::<Tree>(root).next(), Some(child1));
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child2, child3]
+    );
+
+    // Empty parent
+    let grandchild = world.spawn(("Grandchild",));
+    world.attach_front::<Tree>(grandchild, child1).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(child1).collect::<Vec<_>>(),
+        [grandchild]
+    );
+}
+
+#[test]
+fn descendant_count() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let _child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    let _child5 = world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    assert_eq!(world.descendant_count::<Tree>(root), 5);
+    assert_eq!(
+        world.descendant_count::<Tree>(root),
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>()
+            .len()
+    );
+    assert_eq!(world.descendant_count::<Tree>(child2), 2);
+    assert_eq!(world.descendant_count::<Tree>(child4), 0);
+}
+
+#[test]
+fn height() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    assert_eq!(world.height::<Tree>(root), 3);
+    assert_eq!(world.height::<Tree>(child2), 2);
+    assert_eq!(world.height::<Tree>(child1), 0);
+    assert_eq!(world.height::<Tree>(child4), 0);
+}
+
+#[test]
+fn levels() {
+    // Root ---- Child 1
+    //           ------- Child 3
+    //      ---- Child 2
+    //           ------- Child 4
+    //                   ------- Child 6
+    //           ------- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child1, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child2, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(child2, ("Child5",)).unwrap();
+    let child6 = world.attach_new::<Tree, _>(child4, ("Child6",)).unwrap();
+
+    assert_eq!(
+        world.levels::<Tree>(root),
+        [
+            vec![child1, child2],
+            vec![child3, child4, child5],
+            vec![child6]
+        ]
+    );
+    assert_eq!(
+        world.levels::<Tree>(child2),
+        [vec![child4, child5], vec![child6]]
+    );
+    assert!(world.levels::<Tree>(child6).is_empty());
+}
+
+#[test]
+fn detach_children_leaf() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child = world.attach_new::<Tree, _>(root, ("Child",)).unwrap();
+    let loose = world.spawn(("Loose",));
+
+    assert!(world.detach_children::<Tree>(child).unwrap().is_empty());
+    assert!(world.detach_children::<Tree>(loose).unwrap().is_empty());
+
+    assert_eq!(world.parent::<Tree>(child).unwrap(), root);
+    assert_eq!(world.descendant_count::<Tree>(root), 1);
+}
+
+#[test]
+fn children_last() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let leaf = world.spawn(("Leaf",));
+
+    for _ in 0..10 {
+        world.attach_new::<Tree, _>(root, ("Child",)).unwrap();
+    }
+
+    let children = world.children::<Tree>(root).collect::<Vec<_>>();
+    assert_eq!(
+        world.children::<Tree>(root).last(),
+        children.last().copied()
+    );
+
+    // Partially consumed
+    let mut iter = world.children::<Tree>(root);
+    iter.nth(4);
+    assert_eq!(iter.last(), children.last().copied());
+
+    let mut iter = world.children::<Tree>(root);
+    iter.by_ref().for_each(drop);
+    assert_eq!(iter.last(), None);
+
+    assert_eq!(world.children::<Tree>(leaf).last(), None);
+
+    // Despawning without detaching cuts the walk short, while `last` and `count` are still
+    // answered from the parent without walking the list
+    world.despawn(children[4]).unwrap();
+    assert_eq!(
+        world.children::<Tree>(root).fold(None, |_, e| Some(e)),
+        Some(children[3])
+    );
+    assert_eq!(
+        world.children::<Tree>(root).last(),
+        children.last().copied()
+    );
+    assert_eq!(world.children::<Tree>(root).fold(0, |n, _| n + 1), 4);
+    assert_eq!(world.children::<Tree>(root).count(), 10);
+}
+
+#[test]
+fn fold_up() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn((1,));
+    world.attach_new::<Tree, _>(root, (2,)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, (3,)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, (4,)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child3, (5,)).unwrap();
+    world.attach_new::<Tree, _>(root, (6,)).unwrap();
+
+    let value = |e| *world.get::<&i32>(e).unwrap();
+
+    assert_eq!(
+        world.fold_up::<Tree, _, _, _>(root, value, |a, b| a + b),
+        21
+    );
+    assert_eq!(
+        world.fold_up::<Tree, _, _, _>(child2, value, |a, b| a + b),
+        12
+    );
+    assert_eq!(
+        world.fold_up::<Tree, _, _, _>(child4, value, |a, b| a + b),
+        5
+    );
+
+    // Each node is combined with its children in order
+    let order = world.fold_up::<Tree, _, _, _>(
+        root,
+        |e| vec![value(e)],
+        |mut a, b| {
+            a.extend(b);
+            a
+        },
+    );
+    assert_eq!(order, [1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn child_count() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //      ---- Child 4
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+
+    assert_eq!(world.child_count::<Tree>(root), 3);
+    assert_eq!(
+        world.child_count::<Tree>(root),
+        world.children::<Tree>(root).count()
+    );
+    assert_eq!(world.child_count::<Tree>(child2), 1);
+    assert_eq!(world.child_count::<Tree>(child3), 0);
+}
+
+#[test]
+fn child_index() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //      ---- Child 4
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+
+    assert_eq!(world.child_index::<Tree>(child1).unwrap(), 0);
+    assert_eq!(world.child_index::<Tree>(child2).unwrap(), 1);
+    assert_eq!(world.child_index::<Tree>(child4).unwrap(), 2);
+    assert_eq!(world.child_index::<Tree>(child3).unwrap(), 0);
+
+    // Roots have no index
+    assert!(world.child_index::<Tree>(root).is_err());
+}
+
+#[test]
+fn clone_subtree() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    let clone = world
+        .clone_subtree::<Tree, _>(root, |w, e| {
+            let mut builder = EntityBuilderClone::new();
+            builder.add(*w.get::<&&str>(e).unwrap());
+            builder
+        })
+        .unwrap();
+
+    assert_ne!(clone, root);
+    assert_eq!(*world.get::<&&str>(clone).unwrap(), "Root");
+
+    let names = |root| {
+        world
+            .descendants_breadth_first::<Tree>(root)
+            .map(|e| *world.get::<&&str>(e).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names(clone), names(root));
+}
+
+#[test]
+fn capture_subtree() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    let builder = world.capture_subtree::<Tree, _>(root, |e| {
+        let mut builder = EntityBuilderClone::new();
+        builder.add(*world.get::<&&str>(e).unwrap());
+        builder
+    });
+
+    let copy1 = builder.clone().spawn(&mut world);
+    let copy2 = builder.spawn(&mut world);
+
+    let names = |root| {
+        world
+            .subtree::<Tree>(root)
+            .map(|e| *world.get::<&&str>(e).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_ne!(copy1, copy2);
+    assert_eq!(names(copy1), names(root));
+    assert_eq!(names(copy2), names(root));
+}
+
+#[test]
+fn reparent() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //           ------- Child 4
+    //                   ------- Child 5
+    //           ------- Child 6
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child2, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(child4, ("Child5",)).unwrap();
+    let child6 = world.attach_new::<Tree, _>(child2, ("Child6",)).unwrap();
+
+    world.reparent::<Tree>(child4, root, 0).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child4, child1, child2]
+    );
+    assert_eq!(
+        world.children::<Tree>(child2).collect::<Vec<_>>(),
+        [child3, child6]
+    );
+    assert_eq!(world.children::<Tree>(child4).collect::<Vec<_>>(), [child5]);
+    assert_eq!(world.parent::<Tree>(child4).unwrap(), root);
+
+    // Cycles are rejected
+    assert!(world.reparent::<Tree>(child4, child5, 0).is_err());
+    assert!(world.reparent::<Tree>(child4, child4, 0).is_err());
+}
+
+#[test]
+fn detach_removes_child() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child = world.attach_new::<Tree, _>(root, ("Child",)).unwrap();
+    let grandchild = world.attach_new::<Tree, _>(child, ("Grandchild",)).unwrap();
+
+    world.detach::<Tree>(child).unwrap();
+
+    assert!(world.get::<&Child<Tree>>(child).is_err());
+    assert!(world.parent::<Tree>(child).is_err());
+    assert_eq!(
+        world.children::<Tree>(child).collect::<Vec<_>>(),
+        [grandchild]
+    );
+
+    // A second detach does not unlink the former siblings again
+    assert_eq!(world.detach::<Tree>(child).unwrap(), None);
+    assert_eq!(world.children::<Tree>(root).count(), 0);
+}
+
+#[test]
+fn detach_returns_parent() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+
+    assert_eq!(world.detach::<Tree>(child2).unwrap(), Some(child1));
+    assert_eq!(world.detach::<Tree>(child1).unwrap(), Some(root));
+    assert!(world.parent::<Tree>(child1).is_err());
+}
+
+#[test]
+fn leaves() {
+    // Root ---- Child 1
+    //           ------- Child 2
+    //      ---- Child 3
+    //           ------- Child 4
+    //                   ------- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(child4, ("Child5",)).unwrap();
+
+    assert_eq!(
+        world.leaves::<Tree>(root).collect::<Vec<_>>(),
+        [child2, child5]
+    );
+}
+
+#[test]
+fn bfs_skip() {
+    // Root ---- Child 1
+    //           ------- Child 5
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+
+    struct Skip;
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world
+        .attach_new::<Tree, _>(child2, ("Child3", Skip))
+        .unwrap();
+    let _child4 = world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(child1, ("Child5",)).unwrap();
+
+    let order = [child1, child2, child5];
+
+    for child in world.visit_breadth_first::<Tree, _>(root, |w, e| w.try_get::<Skip>(e).is_err()) {
+        println!("{:?}", *world.get::<&&str>(child).unwrap());
+    }
+
+    assert_eq!(
+        world
+            .visit_breadth_first::<Tree, _>(root, |w, e| w.try_get::<Skip>(e).is_err())
+            .collect::<Vec<_>>(),
+        order.to_vec()
+    );
+}
+
+#[test]
+fn bfs_depth() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //           ------- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(child2, ("Child5",)).unwrap();
+
+    assert_eq!(
+        world
+            .descendants_breadth_first_with_depth::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [
+            (child1, 1),
+            (child2, 1),
+            (child3, 2),
+            (child5, 2),
+            (child4, 3)
+        ]
+    );
+}
+
+#[test]
+fn ancestors_inclusive() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let leaf = world.attach_new::<Tree, _>(child2, ("Leaf",)).unwrap();
+
+    assert_eq!(
+        world.ancestors_inclusive::<Tree>(leaf).collect::<Vec<_>>(),
+        [leaf, child2, child1, root]
+    );
+    assert_eq!(
+        world.ancestors_inclusive::<Tree>(root).collect::<Vec<_>>(),
+        [root]
+    );
+}
+
+#[test]
+fn ancestors_rev() {
+    let mut world = World::default();
+    let depth = 10;
+    let root = world.spawn(("Root",));
+
+    let mut chain = vec![root];
+    for _ in 1..depth {
+        let child = world
+            .attach_new::<Tree, _>(*chain.last().unwrap(), ("Child",))
+            .unwrap();
+        chain.push(child);
+    }
+
+    let leaf = chain.pop().unwrap();
+
+    let mut expected = world.ancestors::<Tree>(leaf).collect::<Vec<_>>();
+    expected.reverse();
+
+    assert_eq!(
+        world.ancestors_rev::<Tree>(leaf).collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(world.ancestors_rev::<Tree>(leaf).collect::<Vec<_>>(), chain);
+    assert_eq!(world.ancestors_rev::<Tree>(root).count(), 0);
+}
+
+#[test]
+fn path_from_root() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+
+    assert_eq!(
+        world.path_from_root::<Tree>(child3),
+        [root, child1, child2, child3]
+    );
+    assert_eq!(world.path_from_root::<Tree>(root), [root]);
+}
+
+#[test]
+fn find_child() {
+    struct Marker;
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world
+        .attach_new::<Tree, _>(root, ("Child2", Marker))
+        .unwrap();
+    world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+
+    assert_eq!(
+        world.find_child::<Tree, _>(root, |w, e| w.try_get::<Marker>(e).is_ok()),
+        Some(child2)
+    );
+    assert_eq!(
+        world.find_child::<Tree, _>(child2, |w, e| w.try_get::<Marker>(e).is_ok()),
+        None
+    );
+}
+
+#[test]
+fn attach_all() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let children = (0..4)
+        .map(|i| world.spawn((format!("Child {}", i),)))
+        .collect::<Vec<_>>();
+
+    world.attach_all::<Tree>(root, children.clone()).unwrap();
+
+    assert_eq!(world.children::<Tree>(root).collect::<Vec<_>>(), children);
+    assert_eq!(world.get::<&Parent<Tree>>(root).unwrap().num_children(), 4);
+
+    // Append to an existing list
+    let more = (4..8)
+        .map(|i| world.spawn((format!("Child {}", i),)))
+        .collect::<Vec<_>>();
+
+    world.attach_all::<Tree>(root, more.clone()).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [children, more].concat()
+    );
+    assert_eq!(world.get::<&Parent<Tree>>(root).unwrap().num_children(), 8);
+}
+
+#[test]
+fn attach_all_invalid() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let loose = world.spawn(("Loose",));
+
+    let before = world
+        .descendants_depth_first::<Tree>(root)
+        .collect::<Vec<_>>();
+
+    assert!(matches!(
+        world.attach_all::<Tree>(child2, vec![loose, loose]),
+        Err(HierarchyError::DuplicateChild(e)) if e == loose
+    ));
+    assert!(matches!(
+        world.attach_all::<Tree>(child2, vec![loose, child2]),
+        Err(HierarchyError::Cycle(e)) if e == child2
+    ));
+    assert!(matches!(
+        world.attach_all::<Tree>(child2, vec![loose, child1]),
+        Err(HierarchyError::Cycle(e)) if e == child2
+    ));
+
+    // The rejected calls left the tree untouched
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        before
+    );
+    assert!(world.get::<&Child<Tree>>(loose).is_err());
+    assert!(world.get::<&Parent<Tree>>(child2).is_err());
+    assert!(world.validate::<Tree>(root).is_ok());
+}
+
+#[test]
+fn builder_with_capacity() {
+    let mut builder = TreeBuilder::<Tree>::with_capacity(16);
+    assert!(builder.children().is_empty());
+    assert!(builder.children_mut().capacity() >= 16);
+
+    let mut builder = TreeBuilderClone::<Tree>::with_capacity(16);
+    assert!(builder.children_mut().capacity() >= 16);
+}
+
+#[test]
+fn builder_iter() {
+    let mut builder = TreeBuilder::<Tree>::from(("root",));
+    builder
+        .attach(("child 1",))
+        .attach_tree(
+            TreeBuilder::from(("child 2",))
+                .attach_move(("child 2.1",))
+                .attach_move(("child 2.2",)),
+        )
+        .attach(("child 3",));
+
+    assert_eq!(builder.iter_depth_first().count(), 6);
+    assert!(builder
+        .iter_depth_first()
+        .all(|node| node.root().component_types().count() == 1));
+}
+
+#[test]
+fn detach_all_root() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+
+    world.detach_all::<Tree>(root).unwrap();
+
+    assert_eq!(world.children::<Tree>(root).count(), 0);
+    assert!(world.parent::<Tree>(child1).is_err());
+    assert!(world.parent::<Tree>(child2).is_err());
+}
+
+#[test]
+fn despawn_count() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //      ---- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    assert_eq!(world.despawn_all::<Tree>(child2), 2);
+    assert_eq!(world.despawn_all::<Tree>(root), 4);
+    assert_eq!(world.len(), 0);
+}
+
+#[test]
+fn siblings() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    assert_eq!(
+        world.siblings::<Tree>(child3).collect::<Vec<_>>(),
+        [child1, child2, child4, child5]
+    );
+    assert_eq!(world.siblings::<Tree>(root).count(), 0);
+}
+
+#[test]
+fn rotate_children() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+
+    world.rotate_children::<Tree>(root, 1).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child2, child3, child4, child1]
+    );
+
+    world.rotate_children::<Tree>(root, -2).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child4, child1, child2, child3]
+    );
+
+    world.rotate_children::<Tree>(root, 5).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child2, child3, child4]
+    );
+}
+
+#[test]
+fn root_entities() {
+    let mut world = World::default();
+    let root1 = world.spawn(("Root1",));
+    let root2 = world.spawn(("Root2",));
+    let root3 = world.spawn(("Root3",));
+
+    world.attach_new::<Tree, _>(root1, ("Child1",)).unwrap();
+    world.attach_new::<Tree, _>(root1, ("Child2",)).unwrap();
+    world.attach_new::<Tree, _>(root2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(root1, ("Child4",)).unwrap();
+    world.attach_new::<Tree, _>(root3, ("Child5",)).unwrap();
+
+    let mut expected = [root1, root2, root3];
+    expected.sort();
+
+    let subworld = SubWorldRef::<HierarchyQuery<Tree>>::new(&world);
+
+    let mut roots = subworld.root_entities::<Tree>().unwrap();
+    roots.sort();
+
+    assert_eq!(roots, expected);
+}
+
+#[test]
+fn root_count() {
+    let mut world = World::default();
+    let root1 = world.spawn(("Root1",));
+    let root2 = world.spawn(("Root2",));
+    let root3 = world.spawn(("Root3",));
+
+    let child1 = world.attach_new::<Tree, _>(root1, ("Child1",)).unwrap();
+    world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    world.attach_new::<Tree, _>(root3, ("Child5",)).unwrap();
+
+    assert_eq!(world.root_count::<Tree>().unwrap(), 3);
+
+    let subworld = SubWorldRef::<HierarchyQuery<Tree>>::new(&world);
+    assert_eq!(subworld.root_count::<Tree>().unwrap(), 3);
+}
+
+#[test]
+fn subtree_size() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let _child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let _child4 = world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    let subtree_size = |world: &World, e| world.get::<&Parent<Tree>>(e).unwrap().subtree_size();
+
+    assert_eq!(subtree_size(&world, root), 5);
+    assert_eq!(subtree_size(&world, child2), 2);
+    assert_eq!(subtree_size(&world, child3), 1);
+
+    // Detach a nested subtree
+    world.detach::<Tree>(child3).unwrap();
+    assert_eq!(subtree_size(&world, root), 3);
+    assert_eq!(subtree_size(&world, child2), 0);
+    assert_eq!(subtree_size(&world, child3), 1);
+
+    // Attach it somewhere else
+    world.attach::<Tree>(child3, child5).unwrap();
+    assert_eq!(subtree_size(&world, root), 5);
+    assert_eq!(subtree_size(&world, child5), 2);
+
+    // Despawn a nested subtree
+    world.despawn_all::<Tree>(child3);
+    assert_eq!(subtree_size(&world, root), 3);
+    assert_eq!(subtree_size(&world, child5), 0);
+
+    assert_eq!(
+        world.descendant_count::<Tree>(root),
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>()
+            .len()
+    );
+}
+
+#[test]
+fn validate() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //      ---- Child 4
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+
+    // Despawning without detaching leaves `num_children` out of sync with the list
+    world.despawn(child2).unwrap();
+
+    let defects = world.validate::<Tree>(root).unwrap_err();
+
+    assert!(defects.contains(&HierarchyDefect::ChildCountMismatch {
+        parent: root,
+        num_children: 3,
+        actual: 2,
+    }));
+    assert!(defects.contains(&HierarchyDefect::MissingChild {
+        parent: root,
+        child: child2,
+    }));
+}
+
+#[test]
+fn repair() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //      ---- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    // Repairing a healthy tree does nothing
+    world.repair::<Tree>(root).unwrap();
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [child1, child2, child3, child4, child5]
+    );
+
+    // Despawning without detaching leaves `num_children` out of sync with the list
+    world.despawn(child2).unwrap();
+    assert!(world.validate::<Tree>(root).is_err());
+
+    world.repair::<Tree>(root).unwrap();
+
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+    assert_eq!(world.children::<Tree>(root).count(), 3);
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child4, child5]
+    );
+    assert_eq!(world.descendant_count::<Tree>(root), 3);
+}
+
+#[test]
+fn repair_moved_only_child() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let a = world.attach_new::<Tree, _>(root, ("A",)).unwrap();
+    let b = world.attach_new::<Tree, _>(root, ("B",)).unwrap();
+    let c = world.attach_new::<Tree, _>(a, ("C",)).unwrap();
+
+    // `a` is left as an empty parent still pointing at `c`
+    world.detach::<Tree>(c).unwrap();
+    world.attach::<Tree>(c, b).unwrap();
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+
+    world.repair::<Tree>(root).unwrap();
+
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+    assert_eq!(world.parent::<Tree>(c).unwrap(), b);
+    assert_eq!(world.children::<Tree>(a).count(), 0);
+    assert_eq!(world.children::<Tree>(b).collect::<Vec<_>>(), [c]);
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [a, b, c]
+    );
+}
+
+#[test]
+fn repair_stale_last_child() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+
+    // `last_child` points at a despawned entity, the list is recovered from `first_child`
+    world.despawn(child4).unwrap();
+    world.repair::<Tree>(root).unwrap();
+
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child2]
+    );
+    assert_eq!(world.descendant_count::<Tree>(root), 3);
+
+    // Both ends are stale, the remaining children are found by their parent
+    let child5 = world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+    let child6 = world.attach_new::<Tree, _>(root, ("Child6",)).unwrap();
+    world.despawn(child1).unwrap();
+    world.despawn(child6).unwrap();
+    world.repair::<Tree>(root).unwrap();
+
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child2, child5]
+    );
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [child2, child3, child5]
+    );
+}
+
+#[test]
+fn despawn_children() {
+    // Root ---- Child 1
+    //           ------- Child 2
+    //                   ------- Child 3
+    //      ---- Child 4
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+
+    world.despawn_children::<Tree>(root).unwrap();
+
+    assert!(world.contains(root));
+    for e in [child1, child2, child3, child4] {
+        assert!(!world.contains(e));
+    }
+    assert_eq!(world.children::<Tree>(root).count(), 0);
+}
+
+#[test]
+fn despawn_children_leaf() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child = world.attach_new::<Tree, _>(root, ("Child",)).unwrap();
+    let loose = world.spawn(("Loose",));
+
+    world.despawn_children::<Tree>(child).unwrap();
+    world.despawn_children::<Tree>(loose).unwrap();
+
+    assert!(world.contains(child));
+    assert!(world.contains(loose));
+    assert_eq!(world.parent::<Tree>(child).unwrap(), root);
+    assert_eq!(world.descendant_count::<Tree>(root), 1);
+}
+
+#[test]
+fn replace_child() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let grandchild = world
+        .attach_new::<Tree, _>(child2, ("Grandchild",))
+        .unwrap();
+
+    let new = world.spawn(("New",));
+    world.replace_child::<Tree>(child2, new).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, new, child3]
+    );
+    assert_eq!(world.parent::<Tree>(new).unwrap(), root);
+    assert!(world.parent::<Tree>(child2).is_err());
+
+    // The children of `old` stay with `old`
+    assert_eq!(
+        world.children::<Tree>(child2).collect::<Vec<_>>(),
+        [grandchild]
+    );
+    assert_eq!(world.descendant_count::<Tree>(root), 3);
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+
+    // Replace the last child
+    let last = world.spawn(("Last",));
+    world.replace_child::<Tree>(child3, last).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, new, last]
+    );
+}
+
+#[test]
+fn replace_child_invalid() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let grandchild = world
+        .attach_new::<Tree, _>(child2, ("Grandchild",))
+        .unwrap();
+    let loose = world.spawn(("Loose",));
+    let despawned = world.spawn(("Despawned",));
+    world.despawn(despawned).unwrap();
+
+    let before = world
+        .descendants_depth_first::<Tree>(root)
+        .collect::<Vec<_>>();
+
+    // `old` is not a child, `new` must stay attached
+    assert!(matches!(
+        world.replace_child::<Tree>(loose, child1),
+        Err(HierarchyError::NotAChild(e)) if e == loose
+    ));
+    assert!(matches!(
+        world.replace_child::<Tree>(grandchild, child2),
+        Err(HierarchyError::Cycle(e)) if e == child2
+    ));
+    assert!(matches!(
+        world.replace_child::<Tree>(grandchild, despawned),
+        Err(HierarchyError::NoSuchEntity(e)) if e == despawned
+    ));
+
+    // The rejected calls left the tree untouched
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        before
+    );
+    assert_eq!(world.parent::<Tree>(child1).unwrap(), root);
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+}
+
+#[test]
+fn attach_attached() {
+    let mut world = World::default();
+    let root1 = world.spawn(("Root1",));
+    let root2 = world.spawn(("Root2",));
+    let child1 = world.attach_new::<Tree, _>(root1, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root1, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root1, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(root2, ("Child4",)).unwrap();
+
+    world.attach::<Tree>(child2, root2).unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root1).collect::<Vec<_>>(),
+        [child1, child3]
+    );
+    assert_eq!(
+        world.children::<Tree>(root2).collect::<Vec<_>>(),
+        [child4, child2]
+    );
+    assert_eq!(world.parent::<Tree>(child2).unwrap(), root2);
+    assert_eq!(world.validate::<Tree>(root1), Ok(()));
+    assert_eq!(world.validate::<Tree>(root2), Ok(()));
+}
+
+#[test]
+fn detach_promote_children() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //           ------- Child 4
+    //                   ------- Child 5
+    //      ---- Child 6
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child2, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(child4, ("Child5",)).unwrap();
+    let child6 = world.attach_new::<Tree, _>(root, ("Child6",)).unwrap();
+
+    assert_eq!(world.detach_promote_children::<Tree>(child2).unwrap(), root);
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child3, child4, child6]
+    );
+    assert_eq!(world.get::<&Parent<Tree>>(root).unwrap().num_children(), 4);
+    assert_eq!(world.parent::<Tree>(child3).unwrap(), root);
+    assert_eq!(world.children::<Tree>(child4).collect::<Vec<_>>(), [child5]);
+    assert!(world.parent::<Tree>(child2).is_err());
+    assert_eq!(world.children::<Tree>(child2).count(), 0);
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+
+    // Promote the only child of a parent
+    let root2 = world.spawn(("Root2",));
+    world.attach::<Tree>(child4, root2).unwrap();
+    world.detach_promote_children::<Tree>(child4).unwrap();
+
+    assert_eq!(world.children::<Tree>(root2).collect::<Vec<_>>(), [child5]);
+    assert_eq!(world.validate::<Tree>(root2), Ok(()));
+}
+
+#[test]
+fn dfs_pruned() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //      ---- Child 5
+    //           ------- Child 6
+
+    enum Mark {
+        Skip,
+        Prune,
+    }
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world
+        .attach_new::<Tree, _>(root, ("Child2", Mark::Skip))
+        .unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    let child5 = world
+        .attach_new::<Tree, _>(root, ("Child5", Mark::Prune))
+        .unwrap();
+    let _child6 = world.attach_new::<Tree, _>(child5, ("Child6",)).unwrap();
+
+    let visit = |w: &World, e| match w.get::<&Mark>(e).as_deref() {
+        Ok(Mark::Skip) => Visit::Skip,
+        Ok(Mark::Prune) => Visit::Prune,
+        Err(_) => Visit::Yield,
+    };
+
+    assert_eq!(
+        world
+            .visit_pruned::<Tree, _>(root, visit)
+            .collect::<Vec<_>>(),
+        [child1, child3, child4]
+    );
+
+    // Yield everything
+    assert_eq!(
+        world
+            .visit_pruned::<Tree, _>(root, |_, _| Visit::Yield)
+            .collect::<Vec<_>>(),
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>()
+    );
+
+    // Pruning the root yields nothing
+    assert_eq!(
+        world
+            .visit_pruned::<Tree, _>(root, |_, _| Visit::Prune)
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn for_each_child() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let children = (0..4)
+        .map(|i| world.attach_new::<Tree, _>(root, (i,)).unwrap())
+        .collect::<Vec<_>>();
+
+    world.for_each_child::<Tree, _>(root, |child, cmd| {
+        cmd.write(move |w: &mut World| {
+            *w.get::<&mut i32>(child).unwrap() += 1;
+        });
+    });
+
+    assert_eq!(
+        children
+            .iter()
+            .map(|&e| *world.get::<&i32>(e).unwrap())
+            .collect::<Vec<_>>(),
+        [1, 2, 3, 4]
+    );
+}
+
+#[test]
+fn map_descendants() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //      ---- Child 4
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+
+    assert_eq!(
+        world.map_descendants::<Tree, _, _>(root, |w, e| w.get::<&&str>(e).unwrap().to_string()),
+        ["Child1", "Child2", "Child3", "Child4"]
+    );
+}
+
+#[test]
+fn edges() {
+    let mut world = World::default();
+    let root1 = world.spawn(("Root1",));
+    let root2 = world.spawn(("Root2",));
+    let child1 = world.attach_new::<Tree, _>(root1, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root2, ("Child3",)).unwrap();
+
+    let expected: HashSet<_> = [(child1, root1), (child2, child1), (child3, root2)]
+        .iter()
+        .copied()
+        .collect();
+
+    assert_eq!(world.edges::<Tree>().collect::<HashSet<_>>(), expected);
+}
+
+#[test]
+fn all_nodes() {
+    struct Other;
+
+    let mut world = World::default();
+    let root1 = world.spawn(("Root1",));
+    let root2 = world.spawn(("Root2",));
+    let child1 = world.attach_new::<Tree, _>(root1, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root2, ("Child3",)).unwrap();
+
+    // Not part of the tree
+    let loose = world.spawn(("Loose",));
+    world.attach::<Other>(loose, root1).unwrap();
+
+    let nodes = world.all_nodes::<Tree>().collect::<Vec<_>>();
+    let expected: HashSet<_> = [root1, root2, child1, child2, child3]
+        .iter()
+        .copied()
+        .collect();
+
+    assert_eq!(nodes.len(), expected.len());
+    assert_eq!(nodes.into_iter().collect::<HashSet<_>>(), expected);
+}
+
+#[test]
+fn subtree() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+
+    assert_eq!(world.subtree::<Tree>(root).next(), Some(root));
+    assert_eq!(
+        world.subtree::<Tree>(root).collect::<Vec<_>>(),
+        [root, child1, child2, child3]
+    );
+}
+
+#[test]
+fn sort_children_by() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let a = world.attach_new::<Tree, _>(root, ("a", 3)).unwrap();
+    let b = world.attach_new::<Tree, _>(root, ("b", 1)).unwrap();
+    let c = world.attach_new::<Tree, _>(root, ("c", 2)).unwrap();
+    let d = world.attach_new::<Tree, _>(root, ("d", 1)).unwrap();
+    let e = world.attach_new::<Tree, _>(root, ("e", 3)).unwrap();
+
+    let keys = world
+        .query::<&i32>()
+        .iter()
+        .map(|(e, &key)| (e, key))
+        .collect::<HashMap<_, _>>();
+
+    world
+        .sort_children_by::<Tree, _>(root, |a, b| keys[&a].cmp(&keys[&b]))
+        .unwrap();
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [b, d, c, a, e]
+    );
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+}
+
+#[test]
+fn order_index() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let a = world.spawn(("a",));
+    let b = world.spawn(("b",));
+    let c = world.spawn(("c",));
+
+    world.attach_ordered::<Tree>(a, root).unwrap();
+    world.attach_ordered::<Tree>(b, root).unwrap();
+    world.attach_ordered::<Tree>(c, root).unwrap();
+
+    let indices = |world: &World| {
+        world
+            .children::<Tree>(root)
+            .map(|e| world.get::<&OrderIndex<Tree>>(e).unwrap().index())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(indices(&world), [0, 1, 2]);
+
+    // Rebuild the tree out of order from the persisted indices
+    let root = world.spawn(("Root",));
+    let c = world.spawn(("c", OrderIndex::<Tree>::new(2)));
+    let a = world.spawn(("a", OrderIndex::<Tree>::new(0)));
+    let b = world.spawn(("b", OrderIndex::<Tree>::new(1)));
+
+    world.attach_all::<Tree>(root, vec![c, a, b]).unwrap();
+    world.restore_order::<Tree>(root).unwrap();
+
+    assert_eq!(world.children::<Tree>(root).collect::<Vec<_>>(), [a, b, c]);
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+
+    // Sorting rewrites the indices
+    world
+        .sort_children_by::<Tree, _>(root, |x, y| y.cmp(&x))
+        .unwrap();
+
+    let children = world.children::<Tree>(root).collect::<Vec<_>>();
+    assert_eq!(
+        children
+            .iter()
+            .map(|&e| world.get::<&OrderIndex<Tree>>(e).unwrap().index())
+            .collect::<Vec<_>>(),
+        [0, 1, 2]
+    );
+}
+
+#[test]
+fn builder_spawn_with_entities() {
+    let mut world = World::default();
+    let mut builder = TreeBuilder::<Tree>::from(("root",));
+    builder
+        .attach(("child 1",))
+        .attach_tree(
+            TreeBuilder::from(("child 2",))
+                .attach_move(("child 2.1",))
+                .attach_move(("child 2.2",)),
+        )
+        .attach(("child 3",));
+
+    let (root, entities) = builder.spawn_with_entities(&mut world);
+
+    assert_eq!(entities.len(), 5);
+    assert_eq!(
+        entities,
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn attach_tree() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+
+    let mut builder = TreeBuilder::<Tree>::from(("Child2",));
+    builder.attach(("Child3",));
+
+    let child2 = world.attach_tree(root, builder).unwrap();
+
+    assert_eq!(world.parent::<Tree>(child2).unwrap(), root);
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .map(|e| *world.get::<&&str>(e).unwrap())
+            .collect::<Vec<_>>(),
+        ["Child1", "Child2", "Child3"]
+    );
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child2]
+    );
+    assert_eq!(world.descendant_count::<Tree>(root), 3);
+}
+
+#[test]
+fn builder_spawn_deferred_with_entities() {
+    let mut world = World::default();
+    let mut cmd = CommandBuffer::new();
+    let mut builder = TreeBuilder::<Tree>::from(("root",));
+    builder
+        .attach(("child 1",))
+        .attach_tree(
+            TreeBuilder::from(("child 2",))
+                .attach_move(("child 2.1",))
+                .attach_move(("child 2.2",)),
+        )
+        .attach(("child 3",));
+
+    let (root, entities) = builder.spawn_deferred_with_entities(&world, &mut cmd);
+
+    cmd.execute(&mut world);
+
+    assert_eq!(*world.get::<&&str>(root).unwrap(), "root");
+    assert_eq!(
+        entities
+            .iter()
+            .map(|&e| *world.get::<&&str>(e).unwrap())
+            .collect::<Vec<_>>(),
+        ["child 1", "child 2", "child 2.1", "child 2.2", "child 3"]
+    );
+    assert_eq!(
+        entities,
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn clone_iter() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    let mut dfs = world.descendants_depth_first::<Tree>(root);
+    dfs.next();
+    let cloned = dfs.clone();
+    assert_eq!(cloned.clone().count(), 4);
+    assert_eq!(cloned.collect::<Vec<_>>(), dfs.collect::<Vec<_>>());
+
+    let mut bfs = world.descendants_breadth_first::<Tree>(root);
+    bfs.next();
+    let cloned = bfs.clone();
+    assert_eq!(cloned.clone().count(), 4);
+    assert_eq!(cloned.collect::<Vec<_>>(), bfs.collect::<Vec<_>>());
+}
+
+#[test]
+fn debug_iter() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    world.attach_new::<Tree, _>(child, ("Child2",)).unwrap();
+
+    let children = format!("{:?}", world.children::<Tree>(root));
+    let dfs = format!("{:?}", world.descendants_depth_first::<Tree>(root));
+    let bfs = format!("{:?}", world.descendants_breadth_first::<Tree>(root));
+
+    eprintln!("{}\n{}\n{}", children, dfs, bfs);
+
+    assert!(children.starts_with("ChildrenIter"));
+    assert!(dfs.starts_with("DepthFirstIterator"));
+    assert!(bfs.starts_with("BreadthFirstIterator"));
+    assert!(bfs.contains("Tree"));
+}
+
+#[test]
+fn retag_subtree() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //           ------- Child 5
+
+    struct Other;
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    world.attach_new::<Tree, _>(child2, ("Child5",)).unwrap();
+
+    let expected = world
+        .descendants_depth_first::<Tree>(root)
+        .collect::<Vec<_>>();
+
+    world.retag_subtree::<Tree, Other>(root).unwrap();
+
+    assert_eq!(
+        world
+            .descendants_depth_first::<Other>(root)
+            .collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(world.descendants_depth_first::<Tree>(root).count(), 0);
+    assert_eq!(world.descendant_count::<Other>(root), 5);
+    assert_eq!(world.validate::<Other>(root), Ok(()));
+}
+
+#[test]
+fn graft() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //
+    // Donor ---- Donor 1
+    //       ---- Donor 2
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+
+    let donor = world.spawn(("Donor",));
+    let donor1 = world.attach_new::<Tree, _>(donor, ("Donor1",)).unwrap();
+    let donor2 = world.attach_new::<Tree, _>(donor, ("Donor2",)).unwrap();
+
+    // The donor root must be a root, and may not be grafted into its own tree
+    assert!(world.graft::<Tree>(donor1, child3).is_err());
+    assert!(world.graft::<Tree>(donor, donor2).is_err());
+    assert!(world.graft::<Tree>(donor, donor).is_err());
+
+    world.graft::<Tree>(donor, child3).unwrap();
+
+    assert_eq!(world.parent::<Tree>(donor).unwrap(), child3);
+    assert_eq!(world.root::<Tree>(donor2).unwrap(), root);
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [child1, child2, child3, donor, donor1, donor2]
+    );
+    assert_eq!(world.descendant_count::<Tree>(root), 6);
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+}
+
+#[test]
+fn root_cycle() {
+    let mut world = World::default();
+    let a = world.spawn(("A",));
+    let b = world.attach_new::<Tree, _>(a, ("B",)).unwrap();
+    let c = world.attach_new::<Tree, _>(b, ("C",)).unwrap();
+
+    assert_eq!(world.root::<Tree>(c).unwrap(), a);
+
+    // Move the link of `c` onto `a`, making `a` and `b` each other's parent
+    let link = world.remove_one::<Child<Tree>>(c).unwrap();
+    world.insert_one(a, link).unwrap();
+
+    assert!(world.root::<Tree>(a).is_err());
+    assert!(world.root::<Tree>(b).is_err());
+}
+
+#[test]
+fn ancestors_cycle() {
+    let mut world = World::default();
+    let a = world.spawn(("A",));
+    let b = world.attach_new::<Tree, _>(a, ("B",)).unwrap();
+    let c = world.attach_new::<Tree, _>(b, ("C",)).unwrap();
+    let d = world.attach_new::<Tree, _>(c, ("D",)).unwrap();
+
+    assert_eq!(world.ancestors::<Tree>(d).collect::<Vec<_>>(), [c, b, a]);
+
+    // Move the link of `d` onto `a`, making `a`, `b` and `c` a cycle
+    let link = world.remove_one::<Child<Tree>>(d).unwrap();
+    world.insert_one(a, link).unwrap();
+
+    assert_eq!(world.ancestors::<Tree>(c).collect::<Vec<_>>(), [b, a]);
+    assert_eq!(world.ancestors::<Tree>(a).collect::<Vec<_>>(), [c, b]);
+}
+
+#[test]
+fn dfs_deep() {
+    let depth = 50_000;
+
+    let mut world = World::default();
+    let leaf = world.spawn(("Leaf",));
+
+    // Grow the chain upwards, which keeps each attach cheap
+    let mut root = leaf;
+    for _ in 1..depth {
+        let parent = world.spawn(("Parent",));
+        world.attach::<Tree>(root, parent).unwrap();
+        root = parent;
+    }
+
+    let mut count = 0;
+    let mut last = None;
+    for e in world.descendants_depth_first::<Tree>(root) {
+        count += 1;
+        last = Some(e);
+    }
+
+    assert_eq!(count, depth - 1);
+    assert_eq!(last, Some(leaf));
+}
+
+#[test]
+fn first_child() {
+    let first_child =
+        |world: &World, parent| world.get::<&Parent<Tree>>(parent).unwrap().first_child();
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    assert_eq!(first_child(&world, root), child1);
+
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    assert_eq!(first_child(&world, root), child1);
+
+    let child3 = world.spawn(("Child3",));
+    world.attach_front::<Tree>(child3, root).unwrap();
+    assert_eq!(first_child(&world, root), child3);
+
+    // Removing the current first child
+    world.detach::<Tree>(child3).unwrap();
+    assert_eq!(first_child(&world, root), child1);
+
+    world.detach::<Tree>(child1).unwrap();
+    assert_eq!(first_child(&world, root), child2);
+
+    world.attach_all::<Tree>(root, [child1, child3]).unwrap();
+    assert_eq!(first_child(&world, root), child2);
+
+    world.swap_siblings::<Tree>(child2, child3).unwrap();
+    assert_eq!(first_child(&world, root), child3);
+
+    world.rotate_children::<Tree>(root, 1).unwrap();
+    assert_eq!(first_child(&world, root), child1);
+
+    world.detach::<Tree>(child1).unwrap();
+    world.detach::<Tree>(child2).unwrap();
+    world.detach::<Tree>(child3).unwrap();
+
+    world.attach_all::<Tree>(root, [child2, child1]).unwrap();
+    assert_eq!(first_child(&world, root), child2);
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child2, child1]
+    );
+}
+
+#[test]
+fn validate_first_child() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+
+    let other = world.spawn(("Other",));
+    world.attach_new::<Tree, _>(other, ("OtherChild",)).unwrap();
+
+    // Swap the `Parent` components so that swapping `child1` and `child2` relinks them without
+    // updating the `first_child` of `root`
+    let root_parent = world.remove_one::<Parent<Tree>>(root).unwrap();
+    let other_parent = world.remove_one::<Parent<Tree>>(other).unwrap();
+    world.insert_one(root, other_parent).unwrap();
+    world.insert_one(other, root_parent).unwrap();
+
+    world.swap_siblings::<Tree>(child1, child2).unwrap();
+
+    let root_parent = world.remove_one::<Parent<Tree>>(other).unwrap();
+    let other_parent = world.remove_one::<Parent<Tree>>(root).unwrap();
+    world.insert_one(root, root_parent).unwrap();
+    world.insert_one(other, other_parent).unwrap();
+
+    assert_eq!(
+        world.get::<&Parent<Tree>>(root).unwrap().first_child(),
+        child1
+    );
+    // The links themselves are intact, so only `first_child` is reported
+    assert_eq!(
+        world.validate::<Tree>(root),
+        Err(vec![HierarchyDefect::FirstChildMismatch {
+            parent: root,
+            first_child: child1,
+            expected: child2,
+        }])
+    );
+}
+
+#[test]
+fn children_rev() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child2, ("Child4",)).unwrap();
+
+    assert_eq!(
+        world.children_rev::<Tree>(root).collect::<Vec<_>>(),
+        [child3, child2, child1]
+    );
+    assert_eq!(
+        world.children_rev::<Tree>(child2).collect::<Vec<_>>(),
+        [child4]
+    );
+    assert_eq!(world.children_rev::<Tree>(child1).count(), 0);
+}
+
+#[test]
+fn tree_wrapper() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.spawn(("Child1",));
+
+    let mut tree = world.tree::<Tree>();
+    tree.attach(child1, root).unwrap();
+    let child2 = tree.attach_new(root, ("Child2",)).unwrap();
+    let child3 = tree.attach_new(child2, ("Child3",)).unwrap();
+
+    assert_eq!(tree.parent(child3).unwrap(), child2);
+    assert_eq!(tree.root(child3).unwrap(), root);
+    assert_eq!(tree.children(root).collect::<Vec<_>>(), [child1, child2]);
+    assert_eq!(tree.ancestors(child3).collect::<Vec<_>>(), [child2, root]);
+    assert_eq!(
+        tree.descendants_depth_first(root).collect::<Vec<_>>(),
+        [child1, child2, child3]
+    );
+
+    assert_eq!(tree.detach(child2).unwrap(), Some(root));
+    assert_eq!(tree.children(root).collect::<Vec<_>>(), [child1]);
+
+    assert_eq!(tree.despawn_all(child2), 2);
+    assert!(!world.contains(child3));
+}
+
+#[test]
+fn propagate() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Offset(i32);
+
+    let mut world = World::default();
+    let root = world.spawn((Offset(1),));
+    let child1 = world.attach_new::<Tree, _>(root, (Offset(2),)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, (Offset(3),)).unwrap();
+    // Skipped, passing the offset of `child2` on
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child3, (Offset(4),)).unwrap();
+
+    world.propagate::<Tree, Offset, _>(root, |a, b| Offset(a.0 + b.0));
+
+    let offset = |e| (*world.get::<&Offset>(e).unwrap()).clone();
+
+    assert_eq!(offset(root), Offset(1));
+    assert_eq!(offset(child1), Offset(3));
+    assert_eq!(offset(child2), Offset(4));
+    assert!(world.get::<&Offset>(child3).is_err());
+    assert_eq!(offset(child4), Offset(8));
+}
+
+#[test]
+fn clear_tree() {
+    struct Other;
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+
+    world.attach::<Other>(child2, root).unwrap();
+    world.attach::<Other>(child1, child2).unwrap();
+
+    world.clear_tree::<Tree>();
+
+    assert!(world.root_entities::<Tree>().unwrap().is_empty());
+    assert_eq!(world.parent::<Tree>(child2).ok(), None);
+    assert_eq!(world.children::<Tree>(root).count(), 0);
+    assert!(world.contains(root) && world.contains(child1) && world.contains(child2));
+
+    // Other trees are untouched
+    assert_eq!(
+        world
+            .descendants_depth_first::<Other>(root)
+            .collect::<Vec<_>>(),
+        [child2, child1]
+    );
+    assert_eq!(world.validate::<Other>(root), Ok(()));
+}
+
+#[test]
+fn dfs_cursor() {
+    // Root ---- Child 1
+    //      ---- Child 2
+    //           ------- Child 3
+    //                   ------- Child 4
+    //      ---- Child 5
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    let expected = world
+        .descendants_depth_first::<Tree>(root)
+        .collect::<Vec<_>>();
+
+    let mut cursor = DepthFirstCursor::<Tree>::new(root);
+    let mut steps = Vec::new();
+
+    // Pause halfway, keeping the cursor in the world
+    for _ in 0..2 {
+        steps.push(cursor.step(&world).unwrap());
+    }
+
+    let holder = world.spawn((cursor,));
+    let mut cursor = world.remove_one::<DepthFirstCursor<Tree>>(holder).unwrap();
+
+    while let Some(e) = cursor.step(&world) {
+        steps.push(e);
+    }
+
+    assert_eq!(steps, expected);
+    assert_eq!(cursor.step(&world), None);
+}
+
+#[test]
+fn errors() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+
+    let despawned = world.spawn(("Despawned",));
+    world.despawn(despawned).unwrap();
+
+    assert!(matches!(
+        world.parent::<Tree>(root),
+        Err(HierarchyError::NotAChild(e)) if e == root
+    ));
+    assert!(matches!(
+        world.parent::<Tree>(despawned),
+        Err(HierarchyError::NoSuchEntity(e)) if e == despawned
+    ));
+    assert!(matches!(
+        world.swap_siblings::<Tree>(child1, child2),
+        Err(HierarchyError::NotSiblings(a, b)) if a == child1 && b == child2
+    ));
+    assert!(matches!(
+        world.reparent::<Tree>(child1, child2, 0),
+        Err(HierarchyError::Cycle(e)) if e == child2
+    ));
+    assert!(matches!(
+        world.rotate_children::<Tree>(child2, 1),
+        Err(HierarchyError::NotAParent(e)) if e == child2
+    ));
+}
+
+#[test]
+fn try_parent() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(child1, ("Child2",)).unwrap();
+    let loose = world.spawn(("Loose",));
+
+    assert_eq!(world.try_parent::<Tree>(root), None);
+    assert_eq!(world.try_parent::<Tree>(loose), None);
+    assert_eq!(world.try_parent::<Tree>(child1), Some(root));
+    assert_eq!(world.try_parent::<Tree>(child2), Some(child1));
+
+    if let Some(parent) = world.try_parent::<Tree>(child2) {
+        assert_eq!(world.try_parent::<Tree>(parent), Some(root));
+    }
+}
+
+#[test]
+fn format_tree() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    world
+        .attach_new::<Tree, _>(child1, ("Grandchild",))
+        .unwrap();
+    world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+
+    let formatted =
+        world.format_tree::<Tree, _>(root, |e| world.get::<&&str>(e).unwrap().to_string());
+
+    assert_eq!(
+        formatted,
+        "Root\n|-------- Child1\n          |-------- Grandchild\n|-------- Child2\n"
+    );
+}
+
+#[test]
+fn render_tree() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let grandchild1 = world
+        .attach_new::<Tree, _>(child1, ("Grandchild1",))
+        .unwrap();
+    world
+        .attach_new::<Tree, _>(child1, ("Grandchild2",))
+        .unwrap();
+    world.attach_new::<Tree, _>(grandchild1, ("Leaf",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    world
+        .attach_new::<Tree, _>(child2, ("Grandchild3",))
+        .unwrap();
+
+    let rendered =
+        world.render_tree::<Tree, _>(root, |e| world.get::<&&str>(e).unwrap().to_string());
+
+    assert_eq!(
+        rendered,
+        concat!(
+            "Root\n",
+            "├── Child1\n",
+            "│   ├── Grandchild1\n",
+            "│   │   └── Leaf\n",
+            "│   └── Grandchild2\n",
+            "└── Child2\n",
+            "    └── Grandchild3\n",
+        )
+    );
+}
+
+#[test]
+fn iterate_despawned_child() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let grandchild = world
+        .attach_new::<Tree, _>(child1, ("Grandchild",))
+        .unwrap();
+    world
+        .attach_new::<Tree, _>(child3, ("Grandchild",))
+        .unwrap();
+
+    // Despawn without unlinking, leaving a dangling `next` in `child1`
+    world.despawn(child2).unwrap();
+
+    assert_eq!(world.children::<Tree>(root).collect::<Vec<_>>(), [child1]);
+    // The last child is still linked, so these are answered from the parent
+    assert_eq!(world.children::<Tree>(root).count(), 3);
+    assert_eq!(world.children::<Tree>(root).last(), Some(child3));
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [child1, grandchild]
+    );
+    // `count` trusts the cached subtree size, walking yields what is still linked
+    assert_eq!(world.descendants_depth_first::<Tree>(root).count(), 5);
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .fold(0, |n, _| n + 1),
+        2
+    );
+    assert_eq!(
+        world
+            .descendants_breadth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [child1, grandchild]
+    );
+
+    let mut cursor = DepthFirstCursor::<Tree>::new(root);
+    assert_eq!(cursor.step(&world), Some(child1));
+    assert_eq!(cursor.step(&world), Some(grandchild));
+    assert_eq!(cursor.step(&world), None);
+}
+
+#[test]
+fn deferred_attach_detach() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.spawn(("Child1",));
+    let child2 = world.spawn(("Child2",));
+
+    let mut cmd = CommandBuffer::new();
+    cmd.attach_deferred::<Tree>(child1, root);
+    cmd.attach_deferred::<Tree>(child2, root);
+
+    // Nothing is applied until the buffer is executed
+    assert!(world.parent::<Tree>(child1).is_err());
+
+    cmd.execute(&mut world);
+
+    assert_eq!(world.parent::<Tree>(child1).unwrap(), root);
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child2]
+    );
+
+    cmd.detach_deferred::<Tree>(child1);
+    cmd.execute(&mut world);
+
+    assert!(world.parent::<Tree>(child1).is_err());
+    assert_eq!(world.children::<Tree>(root).collect::<Vec<_>>(), [child2]);
+}
+
+#[test]
+fn builder_wide() {
+    let mut world = World::default();
+    let mut builder = TreeBuilder::<Tree>::with_capacity(1000);
+    builder.add("root");
+    for i in 0..1000_usize {
+        builder.attach((i,));
+    }
+
+    let root = builder.spawn(&mut world);
+
+    let parent = world.get::<&Parent<Tree>>(root).unwrap();
+    assert_eq!(parent.num_children(), 1000);
+    assert_eq!(parent.subtree_size(), 1000);
+    drop(parent);
+
+    assert!(world
+        .children::<Tree>(root)
+        .enumerate()
+        .all(|(i, e)| *world.get::<&usize>(e).unwrap() == i));
+    assert!(world.children_rev::<Tree>(root).eq(world
+        .children::<Tree>(root)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()));
+    assert!(world.validate::<Tree>(root).is_ok());
+}
+
+#[test]
+fn distance() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let grandchild1 = world
+        .attach_new::<Tree, _>(child1, ("Grandchild1",))
+        .unwrap();
+    let grandchild2 = world
+        .attach_new::<Tree, _>(child2, ("Grandchild2",))
+        .unwrap();
+    let other = world.spawn(("Other",));
+
+    assert_eq!(world.distance::<Tree>(root, root), Some(0));
+    assert_eq!(world.distance::<Tree>(root, grandchild1), Some(2));
+    assert_eq!(world.distance::<Tree>(grandchild1, root), Some(2));
+    assert_eq!(world.distance::<Tree>(child1, child2), Some(2));
+    assert_eq!(world.distance::<Tree>(grandchild1, child2), Some(3));
+    assert_eq!(world.distance::<Tree>(grandchild1, grandchild2), Some(4));
+    assert_eq!(world.distance::<Tree>(root, other), None);
+}
+
+#[test]
+fn nth_ancestor() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child = world.attach_new::<Tree, _>(root, ("Child",)).unwrap();
+    let grandchild = world.attach_new::<Tree, _>(child, ("Grandchild",)).unwrap();
+    let leaf = world.attach_new::<Tree, _>(grandchild, ("Leaf",)).unwrap();
+
+    assert_eq!(world.nth_ancestor::<Tree>(leaf, 0), Some(leaf));
+    assert_eq!(world.nth_ancestor::<Tree>(leaf, 1), Some(grandchild));
+    assert_eq!(world.nth_ancestor::<Tree>(leaf, 2), Some(child));
+    assert_eq!(world.nth_ancestor::<Tree>(leaf, 3), Some(root));
+    assert_eq!(world.nth_ancestor::<Tree>(leaf, 4), None);
+    assert_eq!(world.nth_ancestor::<Tree>(root, 1), None);
+}
+
+#[test]
+fn is_leaf_is_root() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child = world.attach_new::<Tree, _>(root, ("Child",)).unwrap();
+    let leaf = world.attach_new::<Tree, _>(child, ("Leaf",)).unwrap();
+    let loose = world.spawn(("Loose",));
+
+    let despawned = world.spawn(("Despawned",));
+    world.despawn(despawned).unwrap();
+
+    assert!(world.is_root::<Tree>(root));
+    assert!(!world.is_leaf::<Tree>(root));
+
+    assert!(!world.is_root::<Tree>(child));
+    assert!(!world.is_leaf::<Tree>(child));
+
+    assert!(!world.is_root::<Tree>(leaf));
+    assert!(world.is_leaf::<Tree>(leaf));
+
+    assert!(world.is_root::<Tree>(loose));
+    assert!(world.is_leaf::<Tree>(loose));
+
+    assert!(!world.is_root::<Tree>(despawned));
+    assert!(!world.is_leaf::<Tree>(despawned));
+}
+
+#[test]
+fn has_children() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child = world.attach_new::<Tree, _>(root, ("Child",)).unwrap();
+    let loose = world.spawn(("Loose",));
+
+    assert!(world.has_children::<Tree>(root));
+    assert!(!world.has_children::<Tree>(child));
+    assert!(!world.has_children::<Tree>(loose));
+
+    // An emptied parent keeps its `Parent` component
+    world.detach::<Tree>(child).unwrap();
+    assert!(!world.has_children::<Tree>(root));
+}
+
+#[test]
+fn descendants_depth_first_limited() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let grandchild = world
+        .attach_new::<Tree, _>(child1, ("Grandchild",))
+        .unwrap();
+    world.attach_new::<Tree, _>(grandchild, ("Leaf",)).unwrap();
+
+    assert_eq!(
+        world
+            .descendants_depth_first_limited::<Tree>(root, 0)
+            .count(),
+        0
+    );
+    assert_eq!(
+        world
+            .descendants_depth_first_limited::<Tree>(root, 1)
+            .collect::<Vec<_>>(),
+        [child1, child2]
+    );
+    assert_eq!(
+        world
+            .descendants_depth_first_limited::<Tree>(root, 2)
+            .collect::<Vec<_>>(),
+        [child1, grandchild, child2]
+    );
+    assert!(world
+        .descendants_depth_first_limited::<Tree>(root, 10)
+        .eq(world.descendants_depth_first::<Tree>(root)));
+}
+
+#[test]
+fn descendants_with() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world
+        .attach_new::<Tree, _>(root, ("Child1", 1_u32))
+        .unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let grandchild1 = world
+        .attach_new::<Tree, _>(child2, ("Grandchild1", 2_u32))
+        .unwrap();
+    world
+        .attach_new::<Tree, _>(child2, ("Grandchild2",))
+        .unwrap();
+    let grandchild3 = world
+        .attach_new::<Tree, _>(child1, ("Grandchild3", 3_u32))
+        .unwrap();
+
+    let mut query = world.query::<&u32>();
+    let view = query.view();
+
+    let values = world
+        .descendants_with::<Tree, _>(root, &view)
+        .map(|(e, v)| (e, *v))
+        .collect::<Vec<_>>();
+    assert_eq!(values, [(child1, 1), (grandchild3, 3), (grandchild1, 2)]);
+
+    // The query data outlives the traversal
+    let values = world
+        .descendants_with::<Tree, _>(child2, &view)
+        .collect::<Vec<_>>();
+    assert_eq!(values, [(grandchild1, &2)]);
+}
+
+#[test]
+fn children_with() {
+    #[derive(Debug, PartialEq)]
+    struct Visible(bool);
+
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world
+        .attach_new::<Tree, _>(root, ("Child1", Visible(true)))
+        .unwrap();
+    world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world
+        .attach_new::<Tree, _>(root, ("Child3", Visible(false)))
+        .unwrap();
+    world
+        .attach_new::<Tree, _>(child3, ("Grandchild", Visible(true)))
+        .unwrap();
+    world.attach_new::<Tree, _>(root, ("Child4",)).unwrap();
+
+    let mut query = world.query::<&Visible>();
+    let view = query.view();
+    let visible = world
+        .children_with::<Tree, _>(root, &view)
+        .map(|(e, visible)| (e, visible.0))
+        .collect::<Vec<_>>();
+
+    assert_eq!(visible, [(child1, true), (child3, false)]);
+}
+
+#[test]
+fn attach_after_before() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+
+    let after_first = world.spawn(("AfterFirst",));
+    let after_last = world.spawn(("AfterLast",));
+    let before_first = world.spawn(("BeforeFirst",));
+    let before_last = world.spawn(("BeforeLast",));
+
+    world.attach_after::<Tree>(after_first, child1).unwrap();
+    world.attach_after::<Tree>(after_last, child2).unwrap();
+    world.attach_before::<Tree>(before_first, child1).unwrap();
+    world
+        .attach_before::<Tree>(before_last, after_last)
+        .unwrap();
+
+    let expected = [
+        before_first,
+        child1,
+        after_first,
+        child2,
+        before_last,
+        after_last,
+    ];
+
+    assert_eq!(world.children::<Tree>(root).collect::<Vec<_>>(), expected);
+    assert!(world
+        .children_rev::<Tree>(root)
+        .eq(expected.iter().rev().copied()));
+    assert!(world.validate::<Tree>(root).is_ok());
+
+    // Moving an attached child within the same parent
+    world
+        .attach_after::<Tree>(before_first, after_last)
+        .unwrap();
+    assert_eq!(world.children::<Tree>(root).next(), Some(child1));
+    assert_eq!(world.children::<Tree>(root).last(), Some(before_first));
+    assert!(world.validate::<Tree>(root).is_ok());
+
+    assert!(matches!(
+        world.attach_after::<Tree>(child1, root),
+        Err(HierarchyError::NotAChild(e)) if e == root
+    ));
+}
+
+#[test]
+fn move_to_front_back() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+
+    world.move_to_back::<Tree>(child1).unwrap();
+
+    assert_eq!(
+        world.get::<&Parent<Tree>>(root).unwrap().last_child(),
+        child1
+    );
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child2, child3, child1]
+    );
+
+    world.move_to_front::<Tree>(child3).unwrap();
+
+    assert_eq!(
+        world.get::<&Parent<Tree>>(root).unwrap().first_child(),
+        child3
+    );
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child3, child2, child1]
+    );
+
+    assert!(world.move_to_back::<Tree>(root).is_err());
+}
+
+#[test]
+fn despawn_all_roots() {
+    struct Other;
+
+    let mut world = World::default();
+    let root1 = world.spawn(("Root1",));
+    let child1 = world.attach_new::<Tree, _>(root1, ("Child1",)).unwrap();
+    world
+        .attach_new::<Tree, _>(child1, ("Grandchild1",))
+        .unwrap();
+
+    let root2 = world.spawn(("Root2",));
+    world.attach_new::<Tree, _>(root2, ("Child2",)).unwrap();
+
+    let other_root = world.spawn(("OtherRoot",));
+    let other_child = world
+        .attach_new::<Other, _>(other_root, ("OtherChild",))
+        .unwrap();
+    let loose = world.spawn(("Loose",));
+
+    assert_eq!(world.despawn_all_roots::<Tree>(), 5);
+
+    assert_eq!(world.all_nodes::<Tree>().count(), 0);
+    assert!(!world.contains(root1));
+    assert!(!world.contains(root2));
+    assert!(world.contains(loose));
+
+    assert_eq!(
+        world.children::<Other>(other_root).collect::<Vec<_>>(),
+        [other_child]
+    );
+}
+
+#[test]
+fn depth_first_size_hint() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    world
+        .attach_new::<Tree, _>(child1, ("Grandchild1",))
+        .unwrap();
+    let grandchild2 = world
+        .attach_new::<Tree, _>(child2, ("Grandchild2",))
+        .unwrap();
+    world.attach_new::<Tree, _>(grandchild2, ("Leaf",)).unwrap();
+
+    let real = world
+        .descendants_depth_first::<Tree>(root)
+        .fold(0, |acc, _| acc + 1);
+    assert_eq!(real, 5);
+
+    let mut iter = world.descendants_depth_first::<Tree>(root);
+    assert_eq!(iter.size_hint(), (real, Some(real)));
+    assert_eq!(world.descendants_depth_first::<Tree>(root).count(), real);
+
+    iter.next();
+    iter.next();
+    assert_eq!(iter.size_hint(), (real - 2, Some(real - 2)));
+    assert_eq!(iter.count(), real - 2);
+
+    let limited = world.descendants_depth_first_limited::<Tree>(root, 1);
+    assert_eq!(limited.size_hint(), (0, Some(real)));
+    assert_eq!(limited.count(), 2);
+
+    assert_eq!(
+        world.descendants_depth_first::<Tree>(child1).size_hint(),
+        (1, Some(1))
+    );
+
+    // Despawning without detaching leaves the cached subtree size stale, which must only ever
+    // overestimate
+    world.despawn(child1).unwrap();
+
+    let iter = world.descendants_depth_first::<Tree>(root);
+    let (lower, upper) = iter.size_hint();
+    let yielded = iter.collect::<Vec<_>>().len();
+
+    assert!(lower <= yielded);
+    assert!(upper.unwrap() >= yielded);
+    assert_eq!(world.descendants_depth_first::<Tree>(root).count(), yielded);
+}
+
+#[test]
+fn children_double_ended() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let children = (0..5)
+        .map(|i| world.attach_new::<Tree, _>(root, (i,)).unwrap())
+        .collect::<Vec<_>>();
+
+    let mut iter = world.children::<Tree>(root);
+    let mut front = Vec::new();
+    let mut back = Vec::new();
+
+    while let Some(e) = iter.next() {
+        front.push(e);
+        if let Some(e) = iter.next_back() {
+            back.push(e);
+        }
+    }
+
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+
+    assert_eq!(front, [children[0], children[1], children[2]]);
+    assert_eq!(back, [children[4], children[3]]);
+
+    let seen = front.iter().chain(&back).collect::<HashSet<_>>();
+    assert_eq!(seen.len(), 5);
+
+    assert_eq!(
+        world.children::<Tree>(root).rev().collect::<Vec<_>>(),
+        children.iter().rev().copied().collect::<Vec<_>>()
+    );
+
+    let mut iter = world.children::<Tree>(root);
+    iter.next_back();
+    assert_eq!(iter.last(), Some(children[3]));
+}
+
+#[test]
+fn breadth_first_double_ended() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let grandchild1 = world
+        .attach_new::<Tree, _>(child1, ("Grandchild1",))
+        .unwrap();
+    world
+        .attach_new::<Tree, _>(child2, ("Grandchild2",))
+        .unwrap();
+    world.attach_new::<Tree, _>(grandchild1, ("Leaf",)).unwrap();
+
+    let forward = world
+        .descendants_breadth_first::<Tree>(root)
+        .collect::<Vec<_>>();
+    let backward = world
+        .descendants_breadth_first::<Tree>(root)
+        .rev()
+        .collect::<Vec<_>>();
+
+    assert_eq!(forward.len(), 5);
+    assert_eq!(backward, forward.iter().rev().copied().collect::<Vec<_>>());
+
+    // Pinch from both ends
+    let mut iter = world.descendants_breadth_first::<Tree>(root);
+    let mut front = Vec::new();
+    let mut back = Vec::new();
+    while let Some(e) = iter.next() {
+        front.push(e);
+        if let Some(e) = iter.next_back() {
+            back.push(e);
+        }
+    }
+
+    back.reverse();
+    front.extend(back);
+    assert_eq!(front, forward);
+}
+
+#[test]
+fn children_inflated_count() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+
+    let other = world.spawn(("Other",));
+    world.attach_new::<Tree, _>(other, ("OtherChild",)).unwrap();
+
+    // Swap the `Parent` components so that detaching `child3` unlinks it from the list of `root`
+    // without updating the `num_children` of `root`
+    let root_parent = world.remove_one::<Parent<Tree>>(root).unwrap();
+    let other_parent = world.remove_one::<Parent<Tree>>(other).unwrap();
+    world.insert_one(root, other_parent).unwrap();
+    world.insert_one(other, root_parent).unwrap();
+
+    world.detach::<Tree>(child3).unwrap();
+
+    let root_parent = world.remove_one::<Parent<Tree>>(other).unwrap();
+    world.insert_one(root, root_parent).unwrap();
+
+    assert_eq!(world.get::<&Parent<Tree>>(root).unwrap().num_children(), 3);
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child2]
+    );
+    assert_eq!(world.children::<Tree>(root).count(), 2);
+    assert_eq!(world.children::<Tree>(root).last(), Some(child2));
+
+    // The stale `last_child` is no longer a child, which ends the reverse iteration
+    assert!(world.children::<Tree>(root).next_back().is_none());
+    assert_eq!(world.children_rev::<Tree>(root).count(), 0);
+
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [child1, child2]
+    );
+    assert_eq!(
+        world
+            .descendants_breadth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [child1, child2]
+    );
+
+    // Unlinking a middle child the same way leaves a well formed list which is shorter than
+    // `num_children`, so the iterators have to stop when wrapping around
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let grandchild = world
+        .attach_new::<Tree, _>(child3, ("Grandchild",))
+        .unwrap();
+
+    let other = world.spawn(("Other",));
+    world.attach_new::<Tree, _>(other, ("OtherChild",)).unwrap();
+
+    let root_parent = world.remove_one::<Parent<Tree>>(root).unwrap();
+    let other_parent = world.remove_one::<Parent<Tree>>(other).unwrap();
+    world.insert_one(root, other_parent).unwrap();
+    world.insert_one(other, root_parent).unwrap();
+
+    world.detach::<Tree>(child2).unwrap();
+
+    let root_parent = world.remove_one::<Parent<Tree>>(other).unwrap();
+    world.insert_one(root, root_parent).unwrap();
+
+    assert_eq!(world.get::<&Parent<Tree>>(root).unwrap().num_children(), 3);
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child3]
+    );
+    assert_eq!(
+        world.children_rev::<Tree>(root).collect::<Vec<_>>(),
+        [child3, child1]
+    );
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [child1, child3, grandchild]
+    );
+
+    // Once the short list is met, the stale subtree size is no longer trusted
+    let mut iter = world.descendants_depth_first::<Tree>(root);
+    iter.by_ref().take(2).for_each(drop);
+    assert_eq!(iter.size_hint().0, 0);
+    assert_eq!(iter.count(), 1);
+    assert_eq!(
+        world
+            .descendants_breadth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        [child1, child3, grandchild]
+    );
+}
+
+#[test]
+fn find_descendant() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let grandchild1 = world.attach_new::<Tree, _>(child1, ("Target",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Target",)).unwrap();
+    world
+        .attach_new::<Tree, _>(child2, ("Grandchild2",))
+        .unwrap();
+
+    let visited = std::cell::RefCell::new(Vec::new());
+    let found = world.find_descendant::<Tree, _>(root, |world, e| {
+        visited.borrow_mut().push(e);
+        *world.get::<&&str>(e).unwrap() == "Target"
+    });
+
+    assert_eq!(found, Some(grandchild1));
+    assert_eq!(visited.into_inner(), [child1, grandchild1]);
+
+    // The predicate may borrow the hierarchy components mutably
+    let found = world
+        .find_descendant::<Tree, _>(child2, |world, e| world.get::<&mut Child<Tree>>(e).is_ok());
+    assert!(found.is_some());
+
+    assert_eq!(
+        world.find_descendant::<Tree, _>(root, |_, e| e == root),
+        None
+    );
+}
+
+#[test]
+fn descendants_bottom_up() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let grandchild1 = world
+        .attach_new::<Tree, _>(child1, ("Grandchild1",))
+        .unwrap();
+    let grandchild2 = world
+        .attach_new::<Tree, _>(child1, ("Grandchild2",))
+        .unwrap();
+    let leaf = world.attach_new::<Tree, _>(grandchild2, ("Leaf",)).unwrap();
+
+    let order = world
+        .descendants_bottom_up::<Tree>(root)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        order,
+        [grandchild1, leaf, grandchild2, child1, child2, root]
+    );
+    assert_eq!(order.last(), Some(&root));
+
+    // Reversed, every parent comes before its children
+    let top_down = order.iter().rev().copied().collect::<Vec<_>>();
+    for (i, &e) in top_down.iter().enumerate() {
+        if let Ok(parent) = world.parent::<Tree>(e) {
+            assert!(top_down[..i].contains(&parent));
+        }
+    }
+
+    let loose = world.spawn(("Loose",));
+    assert_eq!(
+        world
+            .descendants_bottom_up::<Tree>(loose)
+            .collect::<Vec<_>>(),
+        [loose]
+    );
+}
+
+#[test]
+fn to_adjacency() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+    let grandchild1 = world
+        .attach_new::<Tree, _>(child2, ("Grandchild1",))
+        .unwrap();
+    let grandchild2 = world
+        .attach_new::<Tree, _>(child2, ("Grandchild2",))
+        .unwrap();
+
+    // A parent whose children were all detached
+    let other = world.spawn(("Other",));
+    world.attach::<Tree>(other, child1).unwrap();
+    world.detach::<Tree>(other).unwrap();
+
+    world.swap_siblings::<Tree>(child1, child3).unwrap();
+
+    let adjacency = world.to_adjacency::<Tree>(root);
+
+    let mut expected = HashMap::new();
+    expected.insert(root, vec![child3, child2, child1]);
+    expected.insert(child2, vec![grandchild1, grandchild2]);
+
+    assert_eq!(adjacency, expected);
+    assert_eq!(
+        world.to_adjacency::<Tree>(child2),
+        std::iter::once((child2, vec![grandchild1, grandchild2])).collect::<HashMap<_, _>>()
+    );
+}
+
+#[test]
+fn load_adjacency() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let grandchild1 = world
+        .attach_new::<Tree, _>(child1, ("Grandchild1",))
+        .unwrap();
+    world
+        .attach_new::<Tree, _>(child2, ("Grandchild2",))
+        .unwrap();
+    world
+        .attach_new::<Tree, _>(child2, ("Grandchild3",))
+        .unwrap();
+
+    let adjacency = world.to_adjacency::<Tree>(root);
+    let expected = world
+        .descendants_depth_first::<Tree>(root)
+        .collect::<Vec<_>>();
+
+    world.clear_tree::<Tree>();
+    assert_eq!(world.children::<Tree>(root).count(), 0);
+
+    world.load_adjacency::<Tree>(&adjacency).unwrap();
+
+    assert_eq!(
+        world
+            .descendants_depth_first::<Tree>(root)
+            .collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(world.to_adjacency::<Tree>(root), adjacency);
+    assert!(world.validate::<Tree>(root).is_ok());
+
+    // Attaching the root below its own descendant
+    let mut cycle = HashMap::new();
+    cycle.insert(child2, vec![root]);
+    assert!(matches!(
+        world.load_adjacency::<Tree>(&cycle),
+        Err(HierarchyError::Cycle(_))
+    ));
+
+    let mut duplicate = HashMap::new();
+    duplicate.insert(child1, vec![child2, child2]);
+    assert!(matches!(
+        world.load_adjacency::<Tree>(&duplicate),
+        Err(HierarchyError::DuplicateChild(e)) if e == child2
+    ));
+
+    let despawned = world.spawn(("Despawned",));
+    world.despawn(despawned).unwrap();
+
+    let mut missing = HashMap::new();
+    missing.insert(child1, vec![child2, despawned]);
+    assert!(matches!(
+        world.load_adjacency::<Tree>(&missing),
+        Err(HierarchyError::NoSuchEntity(e)) if e == despawned
+    ));
+
+    // The rejected input left the tree untouched
+    assert_eq!(world.to_adjacency::<Tree>(root), adjacency);
+
+    // Swapping `child1` and its child is only valid once both links are applied
+    let mut swap = HashMap::new();
+    swap.insert(grandchild1, vec![child1]);
+    swap.insert(child2, vec![grandchild1]);
+    world.load_adjacency::<Tree>(&swap).unwrap();
+
+    assert_eq!(world.parent::<Tree>(child1).unwrap(), grandchild1);
+    assert_eq!(world.parent::<Tree>(grandchild1).unwrap(), child2);
+    assert_eq!(world.children::<Tree>(root).collect::<Vec<_>>(), [child2]);
+    assert!(world.validate::<Tree>(root).is_ok());
+}
+
+#[test]
+fn detach_root() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child = world.attach_new::<Tree, _>(root, ("Child",)).unwrap();
+    let loose = world.spawn(("Loose",));
+
+    assert_eq!(world.detach::<Tree>(root).unwrap(), None);
+    assert_eq!(world.detach::<Tree>(loose).unwrap(), None);
+    assert_eq!(world.children::<Tree>(root).collect::<Vec<_>>(), [child]);
+
+    assert_eq!(world.detach::<Tree>(child).unwrap(), Some(root));
+    assert_eq!(world.detach::<Tree>(child).unwrap(), None);
+
+    let despawned = world.spawn(("Despawned",));
+    world.despawn(despawned).unwrap();
+    assert!(matches!(
+        world.detach::<Tree>(despawned),
+        Err(HierarchyError::NoSuchEntity(e)) if e == despawned
+    ));
+}
+
+#[test]
+fn parent_children() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let children = (0..4)
+        .map(|i| world.attach_new::<Tree, _>(root, (i,)).unwrap())
+        .collect::<Vec<_>>();
+
+    let parent = world.get::<&Parent<Tree>>(root).unwrap();
+
+    assert_eq!(parent.children(&world).collect::<Vec<_>>(), children);
+    assert!(parent
+        .children(&world)
+        .rev()
+        .eq(children.iter().rev().copied()));
+}
+
+#[test]
+fn move_up_down() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(root, ("Child3",)).unwrap();
+
+    world.move_up::<Tree>(child2).unwrap();
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child2, child1, child3]
+    );
+
+    // Already first
+    world.move_up::<Tree>(child2).unwrap();
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child2, child1, child3]
+    );
+
+    world.move_down::<Tree>(child1).unwrap();
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child2, child3, child1]
+    );
+
+    // Already last
+    world.move_down::<Tree>(child1).unwrap();
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child2, child3, child1]
+    );
+    assert!(world.validate::<Tree>(root).is_ok());
+
+    assert!(world.move_up::<Tree>(root).is_err());
+}
+
+#[test]
+fn sort_subtree_by_key() {
+    let mut world = World::default();
+    let root = world.spawn((0_u32,));
+    for i in [3_u32, 1, 2].iter() {
+        let child = world.attach_new::<Tree, _>(root, (*i * 10,)).unwrap();
+        for j in [2_u32, 3, 1].iter() {
+            let grandchild = world.attach_new::<Tree, _>(child, (*i * 10 + j,)).unwrap();
+            world.attach_new::<Tree, _>(grandchild, (100 - j,)).unwrap();
+            world.attach_new::<Tree, _>(grandchild, (100 + j,)).unwrap();
+        }
+    }
+
+    world
+        .sort_subtree_by_key::<Tree, _, _>(root, |world, e| *world.get::<&u32>(e).unwrap())
+        .unwrap();
+
+    for parent in world.subtree::<Tree>(root) {
+        let keys = world
+            .children::<Tree>(parent)
+            .map(|e| *world.get::<&u32>(e).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(keys.windows(2).all(|w| w[0] <= w[1]), "{:?}", keys);
+    }
+
+    assert_eq!(
+        world
+            .children::<Tree>(root)
+            .map(|e| *world.get::<&u32>(e).unwrap())
+            .collect::<Vec<_>>(),
+        [10, 20, 30]
+    );
+    assert!(world.validate::<Tree>(root).is_ok());
+}
+
+#[test]
+fn builder_attach_existing() {
+    let mut world = World::default();
+    let existing = world.spawn(("Existing",));
+    let existing_child = world
+        .attach_new::<Tree, _>(existing, ("ExistingChild",))
+        .unwrap();
+
+    let old_parent = world.spawn(("OldParent",));
+    let moved = world.attach_new::<Tree, _>(old_parent, ("Moved",)).unwrap();
+
+    let mut builder = TreeBuilder::<Tree>::from(("Root",));
+    builder
+        .attach(("Child1",))
+        .attach_existing(existing)
+        .attach_existing(moved);
+
+    let root = builder.spawn(&mut world);
+
+    let children = world.children::<Tree>(root).collect::<Vec<_>>();
+    assert_eq!(children.len(), 3);
+    assert_eq!(*world.get::<&&str>(children[0]).unwrap(), "Child1");
+    assert_eq!(&children[1..], [existing, moved]);
+
+    assert_eq!(world.parent::<Tree>(existing).unwrap(), root);
+    assert_eq!(
+        world.children::<Tree>(existing).collect::<Vec<_>>(),
+        [existing_child]
+    );
+    assert_eq!(world.children::<Tree>(old_parent).count(), 0);
+
+    assert!(world.validate::<Tree>(root).is_ok());
+    assert!(world.validate::<Tree>(old_parent).is_ok());
+    assert_eq!(world.descendant_count::<Tree>(root), 4);
+}
+
+#[test]
+fn extract_subtree() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let child1 = world.attach_new::<Tree, _>(root, ("Child1",)).unwrap();
+    let child2 = world.attach_new::<Tree, _>(root, ("Child2",)).unwrap();
+    let child3 = world.attach_new::<Tree, _>(child2, ("Child3",)).unwrap();
+    let child4 = world.attach_new::<Tree, _>(child3, ("Child4",)).unwrap();
+    let child5 = world.attach_new::<Tree, _>(root, ("Child5",)).unwrap();
+
+    let builder = world.extract_subtree::<Tree, _>(child2, |world, e| {
+        let mut builder = EntityBuilderClone::new();
+        builder.add(*world.get::<&&str>(e).unwrap());
+        builder
+    });
+
+    for e in [child2, child3, child4].iter() {
+        assert!(!world.contains(*e));
+    }
+
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [child1, child5]
+    );
+    assert!(world.validate::<Tree>(root).is_ok());
+
+    // Paste the subtree back
+    let pasted = builder.spawn(&mut world);
+    world.attach::<Tree>(pasted, child1).unwrap();
+
+    assert_eq!(
+        world
+            .subtree::<Tree>(pasted)
+            .map(|e| *world.get::<&&str>(e).unwrap())
+            .collect::<Vec<_>>(),
+        ["Child2", "Child3", "Child4"]
+    );
+    assert_eq!(world.descendant_count::<Tree>(root), 5);
+}
+
+#[test]
+fn sibling_count() {
+    let mut world = World::default();
+    let root = world.spawn(("Root",));
+    let children = (0..5)
+        .map(|i| world.attach_new::<Tree, _>(root, (i,)).unwrap())
+        .collect::<Vec<_>>();
+    let grandchild = world
+        .attach_new::<Tree, _>(children[2], ("Grandchild",))
+        .unwrap();
+
+    assert!(children
+        .iter()
+        .all(|&child| world.sibling_count::<Tree>(child) == 4));
+    assert_eq!(world.sibling_count::<Tree>(grandchild), 0);
+    assert_eq!(world.sibling_count::<Tree>(root), 0);
+}
+
+#[test]
+fn attach_keep_order() {
+    let mut world = World::default();
+
+    let root = world.spawn(("Root",));
+    let children = (0..4)
+        .map(|i| {
+            let child = world.spawn((i,));
+            world.attach_ordered::<Tree>(child, root).unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    // Detach a middle child and re-attach it to the same parent
+    world.detach::<Tree>(children[1]).unwrap();
+    assert_eq!(
+        world.children::<Tree>(root).collect::<Vec<_>>(),
+        [children[0], children[2], children[3]]
+    );
+
+    world.attach_keep_order::<Tree>(children[1], root).unwrap();
+    assert_eq!(world.children::<Tree>(root).collect::<Vec<_>>(), children);
+    assert_eq!(world.validate::<Tree>(root), Ok(()));
+
+    // Children without an index go to the back
+    let e = world.spawn(("e",));
+    world.attach_keep_order::<Tree>(e, root).unwrap();
+    assert_eq!(world.children::<Tree>(root).last(), Some(e));
+}
+
+#[test]
+fn roots_of() {
+    let mut world = World::default();
+
+    let mut a = TreeBuilder::<Tree>::from(("a",));
+    a.attach_tree(TreeBuilder::from(("a1",)).attach_move(("a2",)));
+    let (a, a_entities) = a.spawn_with_entities(&mut world);
+
+    let mut b = TreeBuilder::<Tree>::from(("b",));
+    b.attach(("b1",)).attach(("b2",));
+    let (b, b_entities) = b.spawn_with_entities(&mut world);
+
+    let lone = world.spawn(("lone",));
+
+    let mut entities = a_entities;
+    entities.extend(b_entities);
+    entities.extend(vec![a, b, lone]);
+
+    let roots = world.roots_of::<Tree>(&entities);
+    assert_eq!(roots, [a, a, b, b, a, b, lone]);
+
+    for (&entity, &root) in entities.iter().zip(&roots) {
+        assert_eq!(world.root::<Tree>(entity).unwrap(), root);
+    }
+}
+
+#[test]
+fn observer() {
+    #[derive(Default)]
+    struct Events(Vec<(&'static str, Entity, Entity)>);
+
+    impl HierarchyObserver for Events {
+        fn on_attach(&mut self, child: Entity, parent: Entity) {
+            self.0.push(("attach", child, parent));
+        }
+
+        fn on_detach(&mut self, child: Entity, parent: Entity) {
+            self.0.push(("detach", child, parent));
+        }
+    }
+
+    let mut world = World::default();
+    let mut events = Events::default();
+
+    let a = world.spawn(("a",));
+    let b = world.spawn(("b",));
+    let child = world.spawn(("child",));
+
+    world
+        .attach_with_observer::<Tree, _>(child, a, &mut events)
+        .unwrap();
+    // Moving to another parent reports the detachment first
+    world
+        .attach_with_observer::<Tree, _>(child, b, &mut events)
+        .unwrap();
+    assert_eq!(
+        world
+            .detach_with_observer::<Tree, _>(child, &mut events)
+            .unwrap(),
+        Some(b)
+    );
+    // Detaching a root reports nothing
+    assert_eq!(
+        world
+            .detach_with_observer::<Tree, _>(child, &mut events)
+            .unwrap(),
+        None
+    );
+
+    assert_eq!(
+        events.0,
+        [
+            ("attach", child, a),
+            ("detach", child, a),
+            ("attach", child, b),
+            ("detach", child, b),
+        ]
+    );
+}